@@ -3,10 +3,82 @@ use criterion::Throughput;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use http::Method;
 use rand::{distributions::Alphanumeric, Rng};
-use star_router::{Route, Router};
-use std::iter;
+use star_router::{
+    ConflictPolicy, Path, Route, RouteIndex, RouteMatch, RouteParameter, Router, TreeError,
+};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use url::Url;
 
+/// A route index of static paths only, looked up with a single hash lookup per method.
+#[derive(Clone, Debug, Default)]
+struct FlatIndex {
+    items: HashMap<Method, HashMap<String, String>>,
+}
+
+impl FlatIndex {
+    fn key(path: &Path) -> String {
+        let rendered = path.to_string();
+        let (_, key) = rendered.split_once(' ').unwrap();
+
+        String::from(key)
+    }
+}
+
+impl RouteIndex<String> for FlatIndex {
+    fn add_with(
+        &mut self,
+        path: Path,
+        item: String,
+        policy: ConflictPolicy,
+    ) -> Result<Vec<String>, TreeError> {
+        let key = FlatIndex::key(&path);
+        let paths = self.items.entry(path.get_method().clone()).or_default();
+
+        match (paths.entry(key), policy) {
+            (Entry::Vacant(entry), _) => {
+                entry.insert(item);
+                Result::Ok(Vec::new())
+            }
+            (Entry::Occupied(entry), ConflictPolicy::Reject) => {
+                Result::Err(TreeError::PathAlreadyRegistered {
+                    route: entry.key().clone(),
+                })
+            }
+            (Entry::Occupied(mut entry), ConflictPolicy::ReplaceExisting) => {
+                Result::Ok(vec![entry.insert(item)])
+            }
+            (Entry::Occupied(_), ConflictPolicy::KeepExisting) => Result::Ok(vec![item]),
+        }
+    }
+
+    fn remove(&mut self, path: &Path) -> Result<String, TreeError> {
+        let key = FlatIndex::key(path);
+        self.items
+            .get_mut(path.get_method())
+            .and_then(|paths| paths.remove(&key))
+            .ok_or(TreeError::PathNotFound { path: key })
+    }
+
+    fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, String>, TreeError> {
+        self.items
+            .get(method)
+            .and_then(|paths| paths.get(path))
+            .map(|item| RouteMatch::create(item, RouteParameter::new()))
+            .ok_or_else(|| TreeError::PathNotFound {
+                path: String::from(path),
+            })
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn optimize(&mut self) {
+        self.items.shrink_to_fit();
+    }
+}
+
 pub fn empty_lookup_benchmark(c: &mut Criterion) {
     let router: Router<u64> = Router::new(Url::parse("http://example.com").unwrap());
     c.bench_function("not found", |b| {
@@ -35,8 +107,7 @@ pub fn long_static_route_lookup_benchmark(c: &mut Criterion) {
     let mut router = Router::new(Url::parse("http://example.com").unwrap());
     let items = (0..MAX)
         .map(|_| {
-            iter::repeat(())
-                .take(ITEM_LEN)
+            std::iter::repeat_n((), ITEM_LEN)
                 .map(|_| rng.sample(Alphanumeric))
                 .collect()
         })
@@ -80,8 +151,7 @@ pub fn wide_static_route_lookup_benchmark(c: &mut Criterion) {
 
     let items = (0..MAX)
         .map(|_| {
-            iter::repeat(())
-                .take(ITEM_LEN)
+            std::iter::repeat_n((), ITEM_LEN)
                 .map(|_| {
                     let mut path = String::from("/");
                     path.push(rng.sample(Alphanumeric));
@@ -107,7 +177,7 @@ pub fn wide_static_route_lookup_benchmark(c: &mut Criterion) {
         group.throughput(Throughput::Elements(1));
         group.bench_with_input(BenchmarkId::from_parameter(num), &num, |b, &i| {
             let path = items.get(i - 1).unwrap();
-            b.iter(|| black_box(router.resolve(&Method::GET, &path)));
+            b.iter(|| black_box(router.resolve(&Method::GET, path)));
         });
     }
 }
@@ -123,8 +193,7 @@ pub fn dynamic_route_lookup_benchmark(c: &mut Criterion) {
         .map(|_| {
             let mut item = String::from(":");
             item.push_str(
-                &iter::repeat(())
-                    .take(ITEM_LEN)
+                &std::iter::repeat_n((), ITEM_LEN)
                     .map(|_| rng.sample(Alphanumeric))
                     .collect::<String>(),
             );
@@ -155,8 +224,7 @@ pub fn dynamic_route_lookup_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("dynamic route lookup");
     for size in 0..10 {
         let num = (2 << size) as usize;
-        let path = iter::repeat(())
-            .take(num)
+        let path = std::iter::repeat_n((), num)
             .map(|_| String::from(rng.sample(Alphanumeric)))
             .collect::<Vec<String>>()
             .join("/");
@@ -184,8 +252,7 @@ pub fn wildcard_route_lookup_benchmark(c: &mut Criterion) {
         group.throughput(Throughput::Elements(num as u64));
         let path = (0..num)
             .map(|_| {
-                iter::repeat(())
-                    .take(ITEM_LEN)
+                std::iter::repeat_n((), ITEM_LEN)
                     .map(|_| rng.sample(Alphanumeric))
                     .collect()
             })
@@ -197,6 +264,184 @@ pub fn wildcard_route_lookup_benchmark(c: &mut Criterion) {
     }
 }
 
+pub fn path_split_lookup_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("path splitting");
+    for depth in [4, 16, 64] {
+        let segments = (0..depth)
+            .map(|i| format!("/segment{}", i))
+            .collect::<String>();
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("static", Method::GET, &segments, 1).unwrap())
+            .unwrap()
+            .add(Route::create("files", Method::GET, "/files/*path", 2).unwrap())
+            .unwrap();
+        // The router is not optimized, so that the static path is looked up in the tree
+        // instead of the map of the static routes.
+        let remainder = format!("/files{}", segments);
+
+        group.throughput(Throughput::Elements(depth as u64));
+        group.bench_with_input(
+            BenchmarkId::new("long static", depth),
+            &segments,
+            |b, path| b.iter(|| black_box(router.resolve(&Method::GET, path).unwrap())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("wildcard remainder", depth),
+            &remainder,
+            |b, path| b.iter(|| black_box(router.resolve(&Method::GET, path).unwrap())),
+        );
+    }
+}
+
+pub fn fast_path_lookup_benchmark(c: &mut Criterion) {
+    let mut router = Router::new(Url::parse("http://example.com").unwrap());
+    router
+        .add(Route::create("healthz", Method::GET, "/healthz", 1).unwrap())
+        .unwrap();
+    router
+        .add(Route::create("user", Method::GET, "/user/:id", 2).unwrap())
+        .unwrap();
+    let mut fast_router = router.clone();
+    fast_router.set_fast_path(Method::GET, "/healthz", 1);
+
+    let router = router.optimize();
+    let fast_router = fast_router.optimize();
+
+    let mut group = c.benchmark_group("health check lookup");
+    group.bench_function("tree", |b| {
+        b.iter(|| black_box(router.resolve(&Method::GET, "/healthz").unwrap()))
+    });
+    group.bench_function("fast path", |b| {
+        b.iter(|| black_box(fast_router.resolve(&Method::GET, "/healthz").unwrap()))
+    });
+}
+
+pub fn method_count_lookup_benchmark(c: &mut Criterion) {
+    let methods = [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::PATCH,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::TRACE,
+    ];
+
+    let mut group = c.benchmark_group("method count lookup");
+    for &num in &[1, 8] {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        for (i, method) in methods.iter().take(num).enumerate() {
+            router
+                .add(Route::create(&format!("users.{}", i), method.clone(), "/users", i).unwrap())
+                .unwrap();
+        }
+        let router = router.optimize();
+        let method = &methods[num - 1];
+
+        group.bench_with_input(BenchmarkId::from_parameter(num), &num, |b, _| {
+            b.iter(|| black_box(router.resolve(method, "/users").unwrap()))
+        });
+    }
+}
+
+pub fn batch_lookup_benchmark(c: &mut Criterion) {
+    const MAX: usize = 1024;
+    let mut router = Router::new(Url::parse("http://example.com").unwrap());
+    router
+        .add(Route::create("user", Method::GET, "/user/:id", 1).unwrap())
+        .unwrap();
+    router
+        .add(Route::create("post", Method::GET, "/user/:id/post/:post", 2).unwrap())
+        .unwrap();
+    let router = router.optimize();
+
+    let paths = (0..MAX)
+        .map(|i| {
+            if i % 2 == 0 {
+                format!("/user/{}", i)
+            } else {
+                format!("/user/{}/post/{}", i, i)
+            }
+        })
+        .collect::<Vec<String>>();
+    let reqs = paths
+        .iter()
+        .map(|path| (Method::GET, path.as_str()))
+        .collect::<Vec<(Method, &str)>>();
+
+    let mut group = c.benchmark_group("batch lookup");
+    group.throughput(Throughput::Elements(MAX as u64));
+    group.bench_function("resolve", |b| {
+        b.iter(|| {
+            black_box(
+                reqs.iter()
+                    .map(|(method, path)| router.resolve(method, path))
+                    .collect::<Vec<_>>(),
+            )
+        })
+    });
+    group.bench_function("resolve_batch", |b| {
+        b.iter(|| black_box(router.resolve_batch(&reqs)))
+    });
+    #[cfg(feature = "rayon")]
+    group.bench_function("par_resolve_batch", |b| {
+        b.iter(|| black_box(router.par_resolve_batch(&reqs)))
+    });
+}
+
+pub fn index_backend_lookup_benchmark(c: &mut Criterion) {
+    const ITEM_LEN: usize = 8;
+    const DEPTH: usize = 3;
+    const MAX: usize = 1024;
+
+    let mut rng = rand::thread_rng();
+    let paths = (0..MAX)
+        .map(|_| {
+            (0..DEPTH)
+                .map(|_| {
+                    let segment: String = std::iter::repeat_n((), ITEM_LEN)
+                        .map(|_| rng.sample(Alphanumeric))
+                        .collect();
+                    format!("/{}", segment)
+                })
+                .collect()
+        })
+        .collect::<Vec<String>>();
+
+    // The routers are not optimized, since that would resolve the static routes from a map
+    // before the index is walked.
+    let base = Url::parse("http://example.com").unwrap();
+    let mut tree_router = Router::new(base.clone());
+    let mut flat_router = Router::with_index(base, FlatIndex::default());
+    for (i, path) in paths.iter().enumerate() {
+        tree_router
+            .add(Route::create(path, Method::GET, path, i).unwrap())
+            .unwrap();
+        flat_router
+            .add(Route::create(path, Method::GET, path, i).unwrap())
+            .unwrap();
+    }
+
+    let mut group = c.benchmark_group("index backend");
+    group.throughput(Throughput::Elements(MAX as u64));
+    group.bench_function("tree", |b| {
+        b.iter(|| {
+            for path in paths.iter() {
+                black_box(tree_router.resolve(&Method::GET, path).unwrap());
+            }
+        })
+    });
+    group.bench_function("flat map", |b| {
+        b.iter(|| {
+            for path in paths.iter() {
+                black_box(flat_router.resolve(&Method::GET, path).unwrap());
+            }
+        })
+    });
+}
+
 criterion_group!(
     benches,
     empty_lookup_benchmark,
@@ -205,5 +450,10 @@ criterion_group!(
     wide_static_route_lookup_benchmark,
     dynamic_route_lookup_benchmark,
     wildcard_route_lookup_benchmark,
+    path_split_lookup_benchmark,
+    fast_path_lookup_benchmark,
+    method_count_lookup_benchmark,
+    batch_lookup_benchmark,
+    index_backend_lookup_benchmark,
 );
 criterion_main!(benches);
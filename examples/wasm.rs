@@ -0,0 +1,39 @@
+//! Client side routing example.
+//!
+//! The router only depends on crates that compile to WebAssembly, and resolving a route does not
+//! use threads or `std::time`. This example can be built for the browser with:
+//!
+//! ```text
+//! cargo build --example wasm --target wasm32-unknown-unknown
+//! ```
+
+use http::Method;
+use star_router::{Route, Router};
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Page {
+    Home,
+    Article,
+}
+
+fn main() {
+    let mut router = Router::new(Url::parse("https://example.com").unwrap());
+
+    router
+        .add(Route::create("home", Method::GET, "/", Page::Home).unwrap())
+        .unwrap();
+    router
+        .add(Route::create("article", Method::GET, "/articles/:slug", Page::Article).unwrap())
+        .unwrap();
+
+    let router = router.optimize();
+
+    let route_match = router.resolve(&Method::GET, "/articles/hello").unwrap();
+    assert_eq!(route_match.get_item(), &Page::Article);
+    assert_eq!(route_match.get_params().get("slug").unwrap(), "hello");
+    assert_eq!(
+        router.resolve(&Method::GET, "/").unwrap().get_item(),
+        &Page::Home
+    );
+}
@@ -0,0 +1,320 @@
+use crate::path::{Path, STANDARD_METHODS};
+use crate::tree::Tree;
+use crate::RouteMatch;
+use crate::TraceStep;
+use crate::TreeError;
+use http::Method;
+use std::fmt::Debug;
+
+/// Options of a route index lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LookupOptions {
+    case_insensitive: bool,
+    decode_params: bool,
+}
+
+impl LookupOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        LookupOptions::default()
+    }
+
+    /// Whether the static segments are matched ignoring their case.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Set whether the static segments are matched ignoring their case.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) -> &mut Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Whether the path segments are percent-decoded before matching.
+    pub fn is_decode_params(&self) -> bool {
+        self.decode_params
+    }
+
+    /// Set whether the path segments are percent-decoded before matching.
+    pub fn set_decode_params(&mut self, decode_params: bool) -> &mut Self {
+        self.decode_params = decode_params;
+        self
+    }
+}
+
+/// How a path conflicting with an already registered one is handled.
+///
+/// Paths conflict when they are registered for the same method and segments, or when they have
+/// different parameters or wildcards at the same position.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConflictPolicy {
+    /// The new path is rejected with an error.
+    #[default]
+    Reject,
+    /// The new path replaces the registered ones it conflicts with.
+    ReplaceExisting,
+    /// The new path is ignored and the registered ones are kept.
+    KeepExisting,
+}
+
+/// Size statistics of a router.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RouterStats {
+    node_count: usize,
+    max_depth: usize,
+    route_count: usize,
+    dynamic_node_count: usize,
+}
+
+impl RouterStats {
+    /// Number of nodes in the index, including the root.
+    pub fn get_node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Number of segments of the deepest node.
+    pub fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Number of registered routes.
+    pub fn get_route_count(&self) -> usize {
+        self.route_count
+    }
+
+    /// Number of nodes of parameters, optional parameters and wildcards.
+    pub fn get_dynamic_node_count(&self) -> usize {
+        self.dynamic_node_count
+    }
+
+    pub(crate) fn add_node(&mut self, depth: usize, dynamic: bool, routes: usize) {
+        self.node_count += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.route_count += routes;
+        if dynamic {
+            self.dynamic_node_count += 1;
+        }
+    }
+
+    pub(crate) fn set_route_count(&mut self, route_count: usize) {
+        self.route_count = route_count;
+    }
+}
+
+/// Stores route paths and resolves request paths to the items registered for them.
+///
+/// The router uses the tree implementation by default, but any other data structure can be
+/// plugged in with [`Router::with_index`](struct.Router.html#method.with_index).
+pub trait RouteIndex<T>: Clone + Debug {
+    /// Register an item for a path.
+    ///
+    /// By default the item is registered with [`add_with`](#tymethod.add_with), rejecting the
+    /// conflicting paths.
+    fn add(&mut self, path: Path, item: T) -> Result<(), TreeError> {
+        self.add_with(path, item, ConflictPolicy::Reject)
+            .map(|_| ())
+    }
+
+    /// Register an item for a path, handling conflicts with the given policy.
+    ///
+    /// The items that are not registered anymore are returned: the replaced ones, or the given
+    /// one when it was ignored.
+    fn add_with(
+        &mut self,
+        path: Path,
+        item: T,
+        policy: ConflictPolicy,
+    ) -> Result<Vec<T>, TreeError>;
+
+    /// Remove the item registered for a path.
+    fn remove(&mut self, path: &Path) -> Result<T, TreeError>;
+
+    /// Find the item registered for the given method and path.
+    fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, T>, TreeError>;
+
+    /// Find the item registered for the given method and path with the given options.
+    ///
+    /// When the path matched case insensitively and its casing differs from the registered one,
+    /// the path with the registered casing is returned as well. By default the options are
+    /// ignored.
+    fn lookup_with(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        let _ = options;
+        self.lookup(method, path)
+            .map(|route_match| (route_match, Option::None))
+    }
+
+    /// Find the item like [`lookup_with`](#method.lookup_with), recording the branches taken for
+    /// the segments of the path into `trace`.
+    ///
+    /// By default no step is recorded.
+    fn lookup_traced(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+        trace: &mut Vec<TraceStep>,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        let _ = trace;
+        self.lookup_with(method, path, options)
+    }
+
+    /// Whether the given method and path resolve to an item.
+    ///
+    /// By default the path is looked up, implementations can skip capturing the parameters.
+    fn contains(&self, method: &Method, path: &str, options: &LookupOptions) -> bool {
+        self.lookup_with(method, path, options).is_ok()
+    }
+
+    /// Return the methods registered for the given path, sorted by their name.
+    ///
+    /// By default every standard method is looked up one by one.
+    fn allowed_methods(
+        &self,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<Vec<Method>, TreeError> {
+        let allowed: Vec<Method> = STANDARD_METHODS
+            .iter()
+            .filter(|method| self.lookup_with(method, path, options).is_ok())
+            .cloned()
+            .collect();
+
+        if allowed.is_empty() {
+            Result::Err(TreeError::PathNotFound {
+                path: String::from(path),
+            })
+        } else {
+            Result::Ok(allowed)
+        }
+    }
+
+    /// Remove every item.
+    fn clear(&mut self);
+
+    /// Return the items of every path starting with the static segments of the prefix, in no
+    /// particular order.
+    ///
+    /// By default no item is returned.
+    fn items_under(&self, prefix: &str) -> Vec<&T> {
+        let _ = prefix;
+        Vec::new()
+    }
+
+    /// Return the items of every path matching the concrete path, for any method, in the order
+    /// the lookup would prefer them.
+    ///
+    /// By default no item is returned.
+    fn matching_items(&self, path: &str) -> Vec<&T> {
+        let _ = path;
+        Vec::new()
+    }
+
+    /// Visit every path with items, with its pattern and its methods sorted by their name.
+    ///
+    /// By default no path is visited.
+    fn walk<F>(&self, f: F)
+    where
+        F: FnMut(&str, &[Method]),
+    {
+        let _ = f;
+    }
+
+    /// Tries to compact the memory footprint of the index.
+    fn optimize(&mut self);
+
+    /// Return the size statistics of the index.
+    ///
+    /// By default no statistics are collected and every number is zero.
+    fn stats(&self) -> RouterStats {
+        RouterStats::default()
+    }
+}
+
+impl<T> RouteIndex<T> for Tree<T>
+where
+    T: Clone + Debug,
+{
+    fn add(&mut self, path: Path, item: T) -> Result<(), TreeError> {
+        self.add(path, item)
+    }
+
+    fn add_with(
+        &mut self,
+        path: Path,
+        item: T,
+        policy: ConflictPolicy,
+    ) -> Result<Vec<T>, TreeError> {
+        self.add_with(path, item, policy)
+    }
+
+    fn remove(&mut self, path: &Path) -> Result<T, TreeError> {
+        self.remove(path)
+    }
+
+    fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, T>, TreeError> {
+        self.lookup(method, path)
+    }
+
+    fn lookup_with(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        self.lookup_with(method, path, options)
+    }
+
+    fn lookup_traced(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+        trace: &mut Vec<TraceStep>,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        self.lookup_traced(method, path, options, trace)
+    }
+
+    fn allowed_methods(
+        &self,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<Vec<Method>, TreeError> {
+        self.allowed_methods(path, options)
+    }
+
+    fn contains(&self, method: &Method, path: &str, options: &LookupOptions) -> bool {
+        self.contains(method, path, options)
+    }
+
+    fn clear(&mut self) {
+        *self = Tree::new();
+    }
+
+    fn items_under(&self, prefix: &str) -> Vec<&T> {
+        self.items_under(prefix)
+    }
+
+    fn matching_items(&self, path: &str) -> Vec<&T> {
+        self.matching_items(path)
+    }
+
+    fn walk<F>(&self, f: F)
+    where
+        F: FnMut(&str, &[Method]),
+    {
+        self.walk(f);
+    }
+
+    fn optimize(&mut self) {
+        self.optimize();
+    }
+
+    fn stats(&self) -> RouterStats {
+        self.stats()
+    }
+}
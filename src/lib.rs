@@ -1,7 +1,6 @@
 #![deny(
     warnings,
     missing_docs,
-    missing_doc_code_examples,
     missing_debug_implementations,
     missing_copy_implementations
 )]
@@ -37,21 +36,61 @@
 //! ```
 //!
 //! See [`Route`](struct.Route.html) and [`RouteMatch`](struct.RouteMatch.html) for more information.
+//!
+//! The crate compiles to `wasm32-unknown-unknown`, so it can be used for client side routing as
+//! well. Features that need threads are kept behind cargo features.
+//!
+//! With the `serde` feature the route table can be serialized and deserialized, and route
+//! matches can be summarized with [`RouteMatchSummary`](struct.RouteMatchSummary.html).
+//!
+//! With the `indexmap` feature the routes of a router are kept in the order they were added, so
+//! [`Router::routes`](struct.Router.html#method.routes) iterates them deterministically.
 
+mod index;
+mod locale;
 mod map;
 mod path;
+mod predicate;
 mod route;
 mod route_match;
 mod router;
+mod trace;
 mod tree;
 
+pub use index::ConflictPolicy;
+pub use index::LookupOptions;
+pub use index::RouteIndex;
+pub use index::RouterStats;
+pub use locale::LOCALE_PARAMETER;
+pub use path::validate_path;
+pub use path::Item;
 pub use path::Path;
 pub use path::PathError;
+pub use path::ANY_METHOD;
+pub use path::WILDCARD_PARAMETER;
+pub use predicate::PredicateContext;
 pub use route::Route;
+pub use route::RouteMeta;
+pub use route_match::MatchedRouteName;
+pub use route_match::OwnedRouteMatch;
+pub use route_match::ParamError;
 pub use route_match::RouteMatch;
+#[cfg(feature = "serde")]
+pub use route_match::RouteMatchSummary;
 pub use route_match::RouteParameter;
+pub use route_match::RouteParameterExt;
+pub use router::DotSegments;
 pub use router::Linker;
+pub use router::ResolveError;
 pub use router::RouteResolver;
 pub use router::Router;
+pub use router::RouterBuilder;
 pub use router::RouterError;
+pub use router::Scope;
+pub use router::TrailingSlash;
+pub use trace::TraceBranch;
+pub use trace::TraceStep;
 pub use tree::TreeError;
+
+#[doc(hidden)]
+pub use http as __http;
@@ -0,0 +1,98 @@
+use crate::tree::PATH_SEPARATOR;
+
+/// Name of the route parameter that captures the locale segment.
+pub const LOCALE_PARAMETER: &str = "__locale";
+
+#[derive(Debug, Clone)]
+pub struct LocaleSegment {
+    supported: Vec<String>,
+    default: Option<String>,
+}
+
+impl LocaleSegment {
+    pub fn new() -> Self {
+        LocaleSegment {
+            supported: Vec::new(),
+            default: Option::None,
+        }
+    }
+
+    pub fn set_supported(&mut self, supported: Vec<String>) -> &mut Self {
+        self.supported = supported;
+        self
+    }
+
+    pub fn set_default(&mut self, default: String) -> &mut Self {
+        self.default = Option::Some(default);
+        self
+    }
+
+    /// Splits the locale off the beginning of the path.
+    ///
+    /// Returns the locale and the rest of the path. When the first segment is not a supported
+    /// locale, the whole path is returned with the default locale, if there is one.
+    pub fn split<'a>(&'a self, path: &'a str) -> Option<(&'a str, &'a str)> {
+        let trimmed = path.trim_start_matches(PATH_SEPARATOR);
+        let (first, rest) = match trimmed.find(PATH_SEPARATOR) {
+            Option::Some(i) => (&trimmed[..i], &trimmed[i..]),
+            Option::None => (trimmed, PATH_SEPARATOR),
+        };
+
+        self.supported
+            .iter()
+            .find(|locale| locale_eq(locale, first))
+            .map(|locale| (locale.as_str(), rest))
+            .or_else(|| self.default.as_ref().map(|locale| (locale.as_str(), path)))
+    }
+}
+
+impl Default for LocaleSegment {
+    fn default() -> Self {
+        LocaleSegment::new()
+    }
+}
+
+/// Compares two locales ignoring case and the `-`/`_` separator difference.
+fn locale_eq(a: &str, b: &str) -> bool {
+    fn normalize(c: char) -> char {
+        match c {
+            '_' => '-',
+            c => c.to_ascii_lowercase(),
+        }
+    }
+
+    a.len() == b.len() && a.chars().map(normalize).eq(b.chars().map(normalize))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::LocaleSegment;
+    use rstest::*;
+
+    #[rstest(
+        path,
+        result,
+        case("/en/about", Option::Some(("en", "/about"))),
+        case("/EN_us/about", Option::Some(("en-US", "/about"))),
+        case("/en", Option::Some(("en", "/"))),
+        case("/about", Option::None)
+    )]
+    fn test_split(path: &str, result: Option<(&str, &str)>) {
+        let mut locale = LocaleSegment::new();
+        locale.set_supported(vec![String::from("en"), String::from("en-US")]);
+
+        assert_eq!(locale.split(path), result);
+    }
+
+    #[rstest]
+    fn test_split_default() {
+        let mut locale = LocaleSegment::new();
+        locale
+            .set_supported(vec![String::from("en")])
+            .set_default(String::from("de"));
+
+        assert_eq!(locale.split("/en/about"), Option::Some(("en", "/about")));
+        assert_eq!(locale.split("/fr/about"), Option::Some(("de", "/fr/about")));
+    }
+}
@@ -1,24 +1,58 @@
-use fnv::FnvHashMap;
-use std::{borrow::Borrow, fmt::Debug, hash::Hash};
+use fnv::FnvBuildHasher;
+#[cfg(feature = "indexmap")]
+use indexmap::{map as index_map, IndexMap};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map, HashMap},
+    fmt::Debug,
+    hash::{BuildHasher, Hash},
+};
 
+/// Hash map used throughout the crate.
+///
+/// It uses FNV hashing by default, which is fast for the short keys of routes. Another hasher
+/// can be used with [`Map::with_hasher`].
 #[derive(Clone, Debug)]
-pub struct Map<K, V>
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: Serialize, V: Serialize",
+        deserialize = "K: Deserialize<'de>, V: Deserialize<'de>, S: BuildHasher + Default"
+    ))
+)]
+pub struct Map<K, V, S = FnvBuildHasher>
 where
     K: Clone + Hash + Eq + Debug,
-    V: Clone + Debug,
+    V: Debug,
 {
-    inner: FnvHashMap<K, V>,
+    inner: HashMap<K, V, S>,
 }
 
 impl<K, V> Map<K, V>
 where
     K: Clone + Eq + Hash + Debug,
-    V: Clone + Debug,
+    V: Debug,
 {
     #[inline]
     pub fn new() -> Self {
+        Map::with_hasher(FnvBuildHasher::default())
+    }
+}
+
+impl<K, V, S> Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
         Map {
-            inner: FnvHashMap::default(),
+            inner: HashMap::with_hasher(hasher),
         }
     }
 
@@ -33,32 +67,46 @@ where
     }
 
     #[inline]
-    pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.inner.get(k)
     }
 
     #[inline]
-    pub fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut V>
+    pub fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.inner.get_key_value(k)
+    }
+
+    #[inline]
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
     {
         self.inner.get_mut(k)
     }
 
     #[inline]
-    pub fn contains_key<Q: ?Sized>(&self, k: &Q) -> bool
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.inner.contains_key(k)
     }
 
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+
     #[inline]
     pub fn optimize(&mut self) -> &Self {
         self.inner.shrink_to_fit();
@@ -71,22 +119,410 @@ where
     }
 
     #[inline]
-    pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = (&'a K, &'a V)> + 'a> {
-        Box::new(self.inner.iter())
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.inner.remove(k)
+    }
+
+    #[inline]
+    pub fn entry(&mut self, k: K) -> hash_map::Entry<'_, K, V> {
+        self.inner.entry(k)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> hash_map::Iter<'_, K, V> {
+        self.inner.iter()
     }
 
     #[inline]
-    pub fn iter_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (&'a K, &'a mut V)> + 'a> {
-        Box::new(self.inner.iter_mut())
+    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, K, V> {
+        self.inner.iter_mut()
     }
 }
 
-impl<K, V> Default for Map<K, V>
+impl<K, V, S> PartialEq for Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug + PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K, V, S> IntoIterator for Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug,
+{
+    type Item = (K, V);
+    type IntoIter = hash_map::IntoIter<K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = hash_map::Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = hash_map::IterMut<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter_mut()
+    }
+}
+
+impl<K, V, S> Default for Map<K, V, S>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Map::with_hasher(S::default())
+    }
+}
+
+/// Number of entries a [`SmallMap`] keeps in a vector before switching to a hash map.
+const SMALL_MAP_LIMIT: usize = 4;
+
+/// A map for a few entries, like the methods of a tree node.
+///
+/// Up to [`SMALL_MAP_LIMIT`] entries are stored in a vector and found by a linear scan, which
+/// is faster and smaller than hashing. Larger maps are promoted to a [`Map`].
+#[derive(Clone, Debug)]
+pub enum SmallMap<K, V>
+where
+    K: Clone + Hash + Eq + Debug,
+    V: Clone + Debug,
+{
+    Small(Vec<(K, V)>),
+    Large(Map<K, V>),
+}
+
+impl<K, V> SmallMap<K, V>
 where
     K: Clone + Eq + Hash + Debug,
     V: Clone + Debug,
 {
+    #[inline]
+    pub fn new() -> Self {
+        SmallMap::Small(Vec::new())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            SmallMap::Small(entries) => entries.len(),
+            SmallMap::Large(map) => map.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn get(&self, k: &K) -> Option<&V> {
+        match self {
+            SmallMap::Small(entries) => entries
+                .iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, value)| value),
+            SmallMap::Large(map) => map.get(k),
+        }
+    }
+
+    #[inline]
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get(k).is_some()
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self {
+            SmallMap::Small(entries) => {
+                if let Option::Some((_, value)) = entries.iter_mut().find(|(key, _)| *key == k) {
+                    return Option::Some(std::mem::replace(value, v));
+                }
+                if entries.len() < SMALL_MAP_LIMIT {
+                    entries.push((k, v));
+                    return Option::None;
+                }
+
+                let mut map = Map::new();
+                for (key, value) in entries.drain(..) {
+                    map.insert(key, value);
+                }
+                map.insert(k, v);
+                *self = SmallMap::Large(map);
+
+                Option::None
+            }
+            SmallMap::Large(map) => map.insert(k, v),
+        }
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        match self {
+            SmallMap::Small(entries) => entries
+                .iter()
+                .position(|(key, _)| key == k)
+                .map(|i| entries.swap_remove(i).1),
+            SmallMap::Large(map) => map.remove(k),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let (small, large) = match self {
+            SmallMap::Small(entries) => (Option::Some(entries.iter()), Option::None),
+            SmallMap::Large(map) => (Option::None, Option::Some(map.iter())),
+        };
+
+        small
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+            .chain(large.into_iter().flatten())
+    }
+
+    /// Shrinks the storage, moving the entries back to a vector when there are few of them.
+    pub fn optimize(&mut self) -> &Self {
+        match self {
+            SmallMap::Small(entries) => entries.shrink_to_fit(),
+            SmallMap::Large(map) if map.len() <= SMALL_MAP_LIMIT => {
+                let entries = map.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                *self = SmallMap::Small(entries);
+            }
+            SmallMap::Large(map) => {
+                map.optimize();
+            }
+        }
+
+        self
+    }
+}
+
+impl<K, V> Default for SmallMap<K, V>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Clone + Debug,
+{
+    fn default() -> Self {
+        SmallMap::new()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+type RouteMapInner<V> = IndexMap<String, V, FnvBuildHasher>;
+#[cfg(not(feature = "indexmap"))]
+type RouteMapInner<V> = Map<String, V>;
+
+/// Map of the routes of a router by their names.
+///
+/// With the `indexmap` feature the insertion order is kept, so the routes are iterated in the
+/// order they were added. Otherwise it is a [`Map`], and the order is unspecified.
+#[derive(Clone, Debug)]
+pub struct RouteMap<V: Debug> {
+    inner: RouteMapInner<V>,
+}
+
+impl<V: Debug> RouteMap<V> {
+    #[inline]
+    pub fn new() -> Self {
+        RouteMap {
+            inner: RouteMapInner::default(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[inline]
+    pub fn get(&self, k: &str) -> Option<&V> {
+        self.inner.get(k)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, k: &str) -> Option<&mut V> {
+        self.inner.get_mut(k)
+    }
+
+    #[inline]
+    pub fn contains_key(&self, k: &str) -> bool {
+        self.inner.contains_key(k)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, k: String, v: V) -> Option<V> {
+        self.inner.insert(k, v)
+    }
+
+    /// Removes an entry, keeping the order of the others.
+    #[cfg(feature = "indexmap")]
+    #[inline]
+    pub fn remove(&mut self, k: &str) -> Option<V> {
+        self.inner.shift_remove(k)
+    }
+
+    #[cfg(not(feature = "indexmap"))]
+    #[inline]
+    pub fn remove(&mut self, k: &str) -> Option<V> {
+        self.inner.remove(k)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &V)> {
+        self.inner.iter()
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+
+    #[cfg(feature = "indexmap")]
+    pub fn optimize(&mut self) -> &Self {
+        self.inner.shrink_to_fit();
+        self
+    }
+
+    #[cfg(not(feature = "indexmap"))]
+    pub fn optimize(&mut self) -> &Self {
+        self.inner.optimize();
+        self
+    }
+}
+
+impl<V: Debug> IntoIterator for RouteMap<V> {
+    type Item = (String, V);
+    #[cfg(feature = "indexmap")]
+    type IntoIter = index_map::IntoIter<String, V>;
+    #[cfg(not(feature = "indexmap"))]
+    type IntoIter = hash_map::IntoIter<String, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<V: Debug> Default for RouteMap<V> {
     fn default() -> Self {
-        Map::new()
+        RouteMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Map, RouteMap, SmallMap, SMALL_MAP_LIMIT};
+    use rstest::*;
+    use std::collections::hash_map::RandomState;
+
+    #[rstest]
+    fn test_with_hasher() {
+        let mut map = Map::with_hasher(RandomState::new());
+        assert!(map.is_empty());
+
+        assert_eq!(map.insert(String::from("id"), 1), Option::None);
+        assert_eq!(map.insert(String::from("id"), 2), Option::Some(1));
+        assert_eq!(map.get("id"), Option::Some(&2));
+
+        let mut other = Map::with_hasher(RandomState::new());
+        other.insert(String::from("id"), 2);
+        assert_eq!(map, other);
+
+        *map.entry(String::from("id")).or_insert(0) += 1;
+        *map.entry(String::from("count")).or_insert(0) += 1;
+        assert_eq!(map.get("id"), Option::Some(&3));
+        assert_eq!(map.remove("count"), Option::Some(1));
+        assert_eq!(map.remove("count"), Option::None);
+        map.insert(String::from("id"), 2);
+
+        let mut fnv: Map<String, i32> = Map::default();
+        fnv.insert(String::from("id"), 2);
+        assert_eq!(
+            fnv.into_iter().collect::<Vec<_>>(),
+            vec![(String::from("id"), 2)]
+        );
+    }
+
+    #[rstest]
+    fn test_small_map() {
+        let mut map = SmallMap::new();
+        assert!(map.is_empty());
+
+        for i in 0..SMALL_MAP_LIMIT {
+            assert_eq!(map.insert(i, i * 10), Option::None);
+        }
+        assert!(matches!(map, SmallMap::Small(_)));
+        assert_eq!(map.insert(0, 1), Option::Some(0));
+        assert_eq!(map.insert(0, 0), Option::Some(1));
+
+        assert_eq!(map.insert(SMALL_MAP_LIMIT, 100), Option::None);
+        assert!(matches!(map, SmallMap::Large(_)));
+        assert_eq!(map.len(), SMALL_MAP_LIMIT + 1);
+        for i in 0..SMALL_MAP_LIMIT {
+            assert_eq!(map.get(&i), Option::Some(&(i * 10)));
+        }
+        assert_eq!(map.get(&SMALL_MAP_LIMIT), Option::Some(&100));
+        assert_eq!(map.iter().count(), SMALL_MAP_LIMIT + 1);
+
+        assert_eq!(map.remove(&SMALL_MAP_LIMIT), Option::Some(100));
+        map.optimize();
+        assert!(matches!(map, SmallMap::Small(_)));
+        assert!(map.contains_key(&1));
+        assert_eq!(map.remove(&1), Option::Some(10));
+        assert_eq!(map.remove(&1), Option::None);
+        assert_eq!(map.len(), SMALL_MAP_LIMIT - 1);
+    }
+
+    #[rstest]
+    fn test_route_map() {
+        let mut map = RouteMap::new();
+        for name in ["users", "posts", "comments", "tags"] {
+            assert_eq!(map.insert(String::from(name), name.len()), Option::None);
+        }
+        assert_eq!(map.insert(String::from("posts"), 0), Option::Some(5));
+        assert_eq!(map.remove("posts"), Option::Some(0));
+        assert!(map.contains_key("users") && !map.contains_key("posts"));
+        *map.get_mut("tags").unwrap() += 1;
+        assert_eq!(map.get("tags"), Option::Some(&5));
+        assert_eq!(map.len(), 3);
+        map.reserve(100);
+        assert_eq!(map.len(), 3);
+
+        let mut names: Vec<String> = map.into_iter().map(|(name, _)| name).collect();
+        if cfg!(not(feature = "indexmap")) {
+            names.sort_by_key(|name| ["users", "comments", "tags"].iter().position(|n| n == name));
+        }
+        assert_eq!(names, vec!["users", "comments", "tags"]);
     }
 }
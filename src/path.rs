@@ -1,44 +1,146 @@
 use crate::RouteParameter;
 use http::Method;
+use regex::Regex;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 use thiserror::Error;
 
+/// Name of the route parameter that captures an unnamed wildcard, like in `/static/*`.
+pub const WILDCARD_PARAMETER: &str = "*";
+
+/// Method of the routes that match any method, see
+/// [`Router::add_any_method`](struct.Router.html#method.add_any_method).
+///
+/// Such a route resolves every method that has no route of its own at the same path.
+pub const ANY_METHOD: &str = "*";
+
+/// The standard methods, which a route of [`ANY_METHOD`](constant.ANY_METHOD.html) is listed
+/// with.
+pub(crate) const STANDARD_METHODS: [Method; 9] = [
+    Method::CONNECT,
+    Method::DELETE,
+    Method::GET,
+    Method::HEAD,
+    Method::OPTIONS,
+    Method::PATCH,
+    Method::POST,
+    Method::PUT,
+    Method::TRACE,
+];
+
+/// Returns the method with an uppercase name when its name has lowercase letters, so that
+/// methods differing only by case are the same.
+pub(crate) fn normalize_method(method: &Method) -> Option<Method> {
+    if !method.as_str().bytes().any(|b| b.is_ascii_lowercase()) {
+        return Option::None;
+    }
+
+    Option::Some(
+        Method::from_bytes(method.as_str().to_ascii_uppercase().as_bytes())
+            .expect("an uppercase method is a valid method"),
+    )
+}
+
+/// Escapes a leading `:` or `*` of a static segment, like in `/\:version`.
+pub(crate) const ESCAPE: char = '\\';
+
+/// Checks that a path is syntactically valid without building a route, e.g. the paths of a
+/// configuration file.
+///
+/// The path is parsed like in [`Path::parse`](struct.Path.html#method.parse), the method does
+/// not affect the validation.
+pub fn validate_path(path: &str) -> Result<(), PathError> {
+    Path::parse_get(path).map(drop)
+}
+
 /// Represents a parsed path.
-#[derive(Debug, Clone)]
+///
+/// Paths are equal when their methods, items, greediness and priorities are equal, and for
+/// paths without items, when both or neither of them are the root path.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Path {
     method: Method,
     items: Vec<Item>,
+    constraints: Vec<Option<Constraint>>,
+    greedy: bool,
+    priority: i32,
+    root: bool,
 }
 
 impl Path {
     /// Parses a path.
+    ///
+    /// The name of the method is converted to uppercase.
     pub fn parse(method: Method, path: &str) -> Result<Path, PathError> {
+        let method = normalize_method(&method).unwrap_or(method);
+        let items: Vec<Item> = path
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                if let Option::Some(literal) = part
+                    .strip_prefix(ESCAPE)
+                    .filter(|literal| literal.starts_with([':', '*']))
+                {
+                    return Item::Static(String::from(literal));
+                }
+                let name = String::from(part);
+
+                // The first character can be longer than a byte, so the part is not sliced.
+                match part.chars().next() {
+                    Option::Some(':') if part.len() > 1 && part.ends_with('?') => {
+                        Item::OptionalParameter(name)
+                    }
+                    Option::Some(':') => Item::Parameter(name),
+                    Option::Some('*') => Item::Wildcard(name),
+                    _ => Item::Static(name),
+                }
+            })
+            .collect();
+        let constraints = items
+            .iter()
+            .map(Item::get_constraint)
+            .collect::<Result<Vec<Option<Constraint>>, PathError>>()?;
         let path = Path {
             method,
-            items: path
-                .split('/')
-                .filter(|part| !part.is_empty())
-                .map(|part| {
-                    let name = String::from(part);
-
-                    match &part[..1] {
-                        ":" => Item::Parameter(name),
-                        "*" => Item::Wildcard(name),
-                        _ => Item::Static(name),
-                    }
-                })
-                .collect(),
+            root: items.is_empty() && !path.is_empty(),
+            items,
+            constraints,
+            greedy: false,
+            priority: 0,
         };
 
         path.validate().map(|_| path)
     }
 
+    /// Parses a path of a `GET` route.
+    pub fn parse_get(path: &str) -> Result<Path, PathError> {
+        Path::parse(Method::GET, path)
+    }
+
     fn validate(&self) -> Result<(), PathError> {
+        let mut names: Vec<&str> = Vec::new();
+
         for (i, item) in self.items.iter().enumerate() {
             item.validate()?;
 
+            if !item.is_static() {
+                let name = item.get_parameter_name();
+                if names.contains(&name) {
+                    return Result::Err(PathError::DuplicateParameterName {
+                        name: String::from(name),
+                    });
+                }
+                names.push(name);
+            }
+
             if item.is_wildcard() && i != self.items.len() - 1 {
                 return Result::Err(PathError::WildcardItemMustBeLast);
             }
+
+            if item.is_optional_parameter() && i != self.items.len() - 1 {
+                return Result::Err(PathError::OptionalItemMustBeLast);
+            }
         }
 
         Result::Ok(())
@@ -49,40 +151,275 @@ impl Path {
         &self.method
     }
 
+    /// Whether the path matches requests that are longer than the path itself.
+    pub fn is_greedy(&self) -> bool {
+        self.greedy
+    }
+
+    pub(crate) fn set_greedy(&mut self, greedy: bool) {
+        self.greedy = greedy;
+    }
+
+    /// Returns the priority of the path, 0 by default.
+    ///
+    /// When several parameters or wildcards accept a segment, the path of the higher priority
+    /// is preferred among the ones matching.
+    pub fn get_priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub(crate) fn set_priority(&mut self, priority: i32) {
+        self.priority = priority;
+    }
+
+    /// Returns the specificity of the path, the sum of the scores of its segments: 3 for a
+    /// static segment, 2 for a parameter, optional or not, and 1 for a wildcard.
+    ///
+    /// It is meant for sorting the routes matching a path, e.g. the ones returned by
+    /// [`Router::shadows`](struct.Router.html#method.shadows), from the most specific one.
+    /// The scores follow the order in which the router tries the segments, but the router
+    /// compares the paths segment by segment and also takes their priority into account, so
+    /// the specificity does not decide which route is resolved.
+    pub fn specificity(&self) -> u32 {
+        self.items
+            .iter()
+            .map(|item| match item {
+                Item::Static(_) => 3,
+                Item::Parameter(_) | Item::OptionalParameter(_) => 2,
+                Item::Wildcard(_) => 1,
+            })
+            .sum()
+    }
+
+    /// Parses a path that is prepended to other paths.
+    ///
+    /// Wildcard and optional items are rejected, since they must be the last items of a path.
+    pub(crate) fn parse_prefix(prefix: &str) -> Result<Path, PathError> {
+        let path = Path::parse(Method::GET, prefix)?;
+
+        for item in path.items.iter() {
+            if item.is_wildcard() {
+                return Result::Err(PathError::WildcardItemMustBeLast);
+            }
+            if item.is_optional_parameter() {
+                return Result::Err(PathError::OptionalItemMustBeLast);
+            }
+        }
+
+        Result::Ok(path)
+    }
+
+    /// Returns a copy of the path with the given prefix prepended.
+    pub(crate) fn with_prefix(&self, prefix: &Path) -> Result<Path, PathError> {
+        let path = Path {
+            method: self.method.clone(),
+            items: prefix
+                .items
+                .iter()
+                .chain(self.items.iter())
+                .cloned()
+                .collect(),
+            constraints: prefix
+                .constraints
+                .iter()
+                .chain(self.constraints.iter())
+                .cloned()
+                .collect(),
+            greedy: self.greedy,
+            priority: self.priority,
+            root: (self.root || prefix.root) && self.items.is_empty() && prefix.items.is_empty(),
+        };
+
+        path.validate().map(|_| path)
+    }
+
+    /// Returns the constraints of the items.
+    pub(crate) fn get_constraints(&self) -> &Vec<Option<Constraint>> {
+        &self.constraints
+    }
+
     /// Returns the item of the path.
     pub fn get_items(&self) -> &Vec<Item> {
         &self.items
     }
 
     /// Renders a path with the given parameters.
+    ///
+    /// A missing optional parameter is left out of the rendered path. A missing parameter is
+    /// reported with the zero-based index of its segment. The value of a wildcard may contain
+    /// slashes, its leading and trailing slashes are trimmed, and an empty value is left out.
     pub fn render(&self, params: RouteParameter) -> Result<String, PathError> {
         self.items
             .iter()
-            .map(|item| {
+            .enumerate()
+            .filter_map(|(index, item)| {
                 let name = item.get_name();
                 if item.is_static() {
-                    Result::Ok(name)
-                } else {
-                    params.get(&name[1..]).map(|s| s.as_str()).ok_or_else(|| {
-                        PathError::ParameterNotFound {
-                            parameter: String::from(name),
-                        }
-                    })
+                    return Option::Some(Result::Ok(name));
+                }
+
+                match params.get(item.get_parameter_name()) {
+                    // A wildcard value is spliced as it is, without its surrounding slashes.
+                    Option::Some(value) if item.is_wildcard() => {
+                        Option::Some(value.trim_matches('/'))
+                            .filter(|value| !value.is_empty())
+                            .map(Result::Ok)
+                    }
+                    Option::Some(value) => Option::Some(Result::Ok(value.as_str())),
+                    Option::None if item.is_optional_parameter() => Option::None,
+                    Option::None => Option::Some(Result::Err(PathError::ParameterNotFound {
+                        parameter: String::from(name),
+                        index,
+                    })),
                 }
             })
             .collect::<Result<Vec<&str>, PathError>>()
-            .map(|res| res.join("/"))
+            .map(|res| format!("/{}", res.join("/")))
+    }
+
+    /// Renders the path like [`render`](#method.render), but fails when a parameter is not
+    /// used by the path.
+    pub fn render_strict(&self, params: RouteParameter) -> Result<String, PathError> {
+        let unused = params
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| {
+                !self
+                    .items
+                    .iter()
+                    .any(|item| !item.is_static() && item.get_parameter_name() == key.as_str())
+            })
+            .min();
+
+        match unused {
+            Option::Some(parameter) => Result::Err(PathError::UnusedParameter {
+                parameter: parameter.clone(),
+            }),
+            Option::None => self.render(params),
+        }
+    }
+
+    /// Extracts the parameters from a concrete path, the inverse of [`render`](#method.render).
+    ///
+    /// The items of the path are matched positionally with the segments of the concrete path,
+    /// respecting the parameter constraints. Segments beyond the last item are only accepted
+    /// for greedy paths. A mismatch is reported with the zero-based index of the item that did
+    /// not match, or the number of items when the concrete path is longer.
+    pub fn extract(&self, path: &str) -> Result<RouteParameter, PathError> {
+        let mismatch = |index: usize| PathError::PathMismatch {
+            path: String::from(path),
+            index,
+        };
+        let pieces: Vec<&str> = path.split('/').filter(|piece| !piece.is_empty()).collect();
+        let mut params = RouteParameter::new();
+        let mut consumed = 0;
+
+        for (index, (item, constraint)) in
+            self.items.iter().zip(self.constraints.iter()).enumerate()
+        {
+            let accepts = |piece: &str| constraint.as_ref().is_none_or(|c| c.matches(piece));
+
+            match (item, pieces.get(consumed)) {
+                (Item::Static(name), Option::Some(piece)) if name == piece => {}
+                (Item::Parameter(_), Option::Some(piece))
+                | (Item::OptionalParameter(_), Option::Some(piece))
+                    if accepts(piece) =>
+                {
+                    params.insert(
+                        String::from(item.get_parameter_name()),
+                        String::from(*piece),
+                    );
+                }
+                (Item::OptionalParameter(_), Option::None) => {}
+                (Item::Wildcard(_), Option::Some(_)) => {
+                    let rest = pieces[consumed..].join("/");
+                    if !accepts(&rest) {
+                        return Result::Err(mismatch(index));
+                    }
+                    params.insert(String::from(item.get_parameter_name()), rest);
+                    consumed = pieces.len();
+                    continue;
+                }
+                (Item::Wildcard(_), Option::None) if accepts("") => {
+                    params.insert(String::from(item.get_parameter_name()), String::new());
+                    continue;
+                }
+                _ => return Result::Err(mismatch(index)),
+            }
+
+            consumed += 1;
+        }
+
+        if consumed < pieces.len() && !self.greedy {
+            return Result::Err(mismatch(self.items.len()));
+        }
+
+        Result::Ok(params)
+    }
+
+    /// Renders the path as a template, with the parameter and wildcard names between `open`
+    /// and `close`, e.g. `/users/{id}` for an OpenAPI path.
+    ///
+    /// The constraints and the optional markers of the parameters are left out.
+    pub fn render_template(&self, open: &str, close: &str) -> String {
+        let mut rendered = String::new();
+        for item in self.items.iter() {
+            rendered.push('/');
+            if item.is_static() {
+                rendered.push_str(item.get_name());
+            } else {
+                rendered.push_str(open);
+                rendered.push_str(item.get_parameter_name());
+                rendered.push_str(close);
+            }
+        }
+
+        if rendered.is_empty() {
+            rendered.push('/');
+        }
+
+        rendered
     }
 
     /// Renders the original path.
+    ///
+    /// The root path is rendered as `/`, and other paths without their leading slash. Static
+    /// segments starting with `:` or `*` are escaped, so the path can be parsed again.
     pub fn render_original(&self) -> String {
+        if self.root {
+            return String::from("/");
+        }
+
         self.items
             .iter()
-            .map(Item::get_name)
-            .collect::<Vec<&str>>()
+            .map(|item| {
+                let name = item.get_name();
+                if item.is_static() && name.starts_with([':', '*']) {
+                    format!("{}{}", ESCAPE, name)
+                } else {
+                    String::from(name)
+                }
+            })
+            .collect::<Vec<String>>()
             .join("/")
     }
 
+    /// Renders the original path with a leading slash, e.g. `/users/:id`.
+    ///
+    /// An empty path that is not the root path is rendered as an empty string.
+    pub(crate) fn render_absolute(&self) -> String {
+        if self.is_empty() {
+            self.render_original()
+        } else {
+            format!("/{}", self.render_original())
+        }
+    }
+
+    /// Whether the path was parsed from the root path, like `/`, instead of an empty string.
+    pub fn is_root(&self) -> bool {
+        self.root
+    }
+
     /// Length of the path.
     pub fn len(&self) -> usize {
         self.items.len()
@@ -94,52 +431,208 @@ impl Path {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Paths are displayed with their method and original form, e.g. `GET /users/:id`.
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.render_absolute())
+    }
+}
+
+/// Parses a path of a `GET` route, like [`Path::parse_get`](#method.parse_get).
+///
+/// The method always defaults to `GET`, use [`Path::parse`](#method.parse) for other methods.
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Path::parse_get(path)
+    }
+}
+
+/// Parses a path with its method, like `GET /users/:id`, the inverse of its `Display`.
+///
+/// The method is separated from the path by whitespace, and it is parsed with
+/// `Method::from_bytes`, so extension methods are accepted.
+impl TryFrom<&str> for Path {
+    type Error = PathError;
+
+    fn try_from(line: &str) -> Result<Self, Self::Error> {
+        let line = line.trim();
+        let (method, path) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let method =
+            Method::from_bytes(method.as_bytes()).map_err(|_| PathError::InvalidMethod {
+                method: String::from(method),
+            })?;
+
+        Path::parse(method, path.trim_start())
+    }
+}
+
+/// Parameter and wildcard names may only contain ASCII alphanumerics, `_` and `-`.
+fn is_parameter_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// A segment of a parsed path, see [`Path::get_items`](struct.Path.html#method.get_items).
+///
+/// The variants hold the segment as it was written, e.g. `:id(\d+)` for a constrained
+/// parameter, except for an escaped static segment, which is held without its escape.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Item {
+    /// a segment matched literally, e.g. `users`
     Static(String),
+    /// a required parameter, e.g. `:id`
     Parameter(String),
+    /// an optional parameter, e.g. `:tab?`
+    OptionalParameter(String),
+    /// a wildcard capturing the rest of the path, e.g. `*path` or `*`
     Wildcard(String),
 }
 
 impl Item {
-    pub fn validate(&self) -> Result<(), PathError> {
-        if self.get_name() == "" {
+    pub(crate) fn validate(&self) -> Result<(), PathError> {
+        if self.get_name() == "" || self.get_parameter_name() == "" {
             return Result::Err(PathError::NameMustNotBeEmpty);
         }
 
+        let name = self.get_parameter_name();
+        let unnamed_wildcard = self.is_wildcard() && name == WILDCARD_PARAMETER;
+        if !self.is_static() && !unnamed_wildcard && !name.chars().all(is_parameter_name_char) {
+            return Result::Err(PathError::InvalidParameterName {
+                name: String::from(name),
+            });
+        }
+
         Result::Ok(())
     }
 
+    /// Return the segment as it was written, e.g. `:id(\d+)`.
     pub fn get_name(&self) -> &str {
         match self {
             Item::Static(ref name) => name,
             Item::Parameter(ref name) => name,
+            Item::OptionalParameter(ref name) => name,
             Item::Wildcard(ref name) => name,
         }
     }
 
-    pub fn is_static(&self) -> bool {
-        match self {
-            Item::Static(_) => true,
-            _ => false,
+    /// Return the name of a parameter or a wildcard without its markers and its constraint,
+    /// e.g. `id` for `:id(\d+)`, or the segment itself for a static item.
+    ///
+    /// The name of an unnamed wildcard is [`WILDCARD_PARAMETER`](constant.WILDCARD_PARAMETER.html).
+    /// A marker missing from an item built by hand is not stripped.
+    pub fn get_parameter_name(&self) -> &str {
+        let name = match self {
+            Item::Static(ref name) => return name,
+            Item::Parameter(ref name) => name.strip_prefix(':').unwrap_or(name),
+            Item::OptionalParameter(ref name) => {
+                let name = name.strip_prefix(':').unwrap_or(name);
+                name.strip_suffix('?').unwrap_or(name)
+            }
+            Item::Wildcard(ref name) if name == WILDCARD_PARAMETER => return WILDCARD_PARAMETER,
+            Item::Wildcard(ref name) => name.strip_prefix('*').unwrap_or(name),
+        };
+
+        match name.find('(') {
+            Option::Some(i) => &name[..i],
+            Option::None => name,
         }
     }
 
+    /// Returns the constraint of a parameter.
+    ///
+    /// The constraint is a regular expression written after the parameter name in parentheses,
+    /// and it must match the whole segment: `:id(\d+)` or `:status(active|inactive)`. A trailing
+    /// `i` makes the match case insensitive: `:status(active|inactive)i`. Wildcards cannot have
+    /// a constraint.
+    pub(crate) fn get_constraint(&self) -> Result<Option<Constraint>, PathError> {
+        let name = match self {
+            Item::Parameter(ref name) => &name[..],
+            Item::OptionalParameter(ref name) => name.strip_suffix('?').unwrap_or(name),
+            Item::Wildcard(ref name) if name.contains('(') => {
+                return Result::Err(PathError::InvalidConstraint {
+                    parameter: name.clone(),
+                })
+            }
+            _ => return Result::Ok(Option::None),
+        };
+
+        let start = match name.find('(') {
+            Option::Some(i) => i,
+            Option::None => return Result::Ok(Option::None),
+        };
+
+        let invalid = || PathError::InvalidConstraint {
+            parameter: String::from(self.get_name()),
+        };
+        let end = name
+            .rfind(')')
+            .filter(|end| *end > start)
+            .ok_or_else(invalid)?;
+        let case_insensitive = match &name[end + 1..] {
+            "" => false,
+            "i" => true,
+            _ => return Result::Err(invalid()),
+        };
+
+        Constraint::create(&name[start + 1..end], case_insensitive)
+            .map(Option::Some)
+            .map_err(|_| invalid())
+    }
+
+    /// Return true for a static segment.
+    pub fn is_static(&self) -> bool {
+        matches!(self, Item::Static(_))
+    }
+
+    /// Return true for a required parameter.
     pub fn is_parameter(&self) -> bool {
-        match self {
-            Item::Parameter(_) => true,
-            _ => false,
-        }
+        matches!(self, Item::Parameter(_))
     }
 
+    /// Return true for an optional parameter.
+    pub fn is_optional_parameter(&self) -> bool {
+        matches!(self, Item::OptionalParameter(_))
+    }
+
+    /// Return true for a wildcard.
     pub fn is_wildcard(&self) -> bool {
-        match self {
-            Item::Wildcard(_) => true,
-            _ => false,
-        }
+        matches!(self, Item::Wildcard(_))
     }
 }
 
+/// A regular expression a parameter must match.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pattern: String,
+    case_insensitive: bool,
+    regex: Regex,
+}
+
+impl Constraint {
+    pub fn create(pattern: &str, case_insensitive: bool) -> Result<Self, regex::Error> {
+        let flags = if case_insensitive { "(?i)" } else { "" };
+
+        Result::Ok(Constraint {
+            pattern: String::from(pattern),
+            case_insensitive,
+            regex: Regex::new(&format!("^{}(?:{})$", flags, pattern))?,
+        })
+    }
+
+    pub fn matches(&self, piece: &str) -> bool {
+        self.regex.is_match(piece)
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.case_insensitive == other.case_insensitive
+    }
+}
+
+impl Eq for Constraint {}
+
 /// Router path errors.
 #[derive(Error, Debug, PartialEq)]
 pub enum PathError {
@@ -147,35 +640,130 @@ pub enum PathError {
     #[error("name must not be empty")]
     NameMustNotBeEmpty,
     /// the given parameter is not found
-    #[error("parameter not found: {parameter:?}")]
+    #[error("parameter not found: {parameter:?} at segment {index}")]
     ParameterNotFound {
         /// missing parameter
         parameter: String,
+        /// zero-based index of the segment of the parameter
+        index: usize,
     },
     /// the wildcard item must be the last
     #[error("wildcard item must be last")]
     WildcardItemMustBeLast,
+    /// the parameter constraint is malformed
+    #[error("invalid constraint: {parameter:?}")]
+    InvalidConstraint {
+        /// parameter with the invalid constraint
+        parameter: String,
+    },
+    /// the optional item must be the last
+    #[error("optional item must be last")]
+    OptionalItemMustBeLast,
+    /// the given parameter is not used by the path
+    #[error("unused parameter: {parameter:?}")]
+    UnusedParameter {
+        /// unused parameter
+        parameter: String,
+    },
+    /// the same parameter name is used more than once
+    #[error("duplicate parameter name: {name:?}")]
+    DuplicateParameterName {
+        /// duplicated parameter name
+        name: String,
+    },
+    /// the parameter name contains characters other than ASCII alphanumerics, `_` and `-`
+    #[error("invalid parameter name: {name:?}")]
+    InvalidParameterName {
+        /// invalid parameter name
+        name: String,
+    },
+    /// the method of the path is invalid
+    #[error("invalid method: {method:?}")]
+    InvalidMethod {
+        /// invalid method
+        method: String,
+    },
+    /// the concrete path does not have the shape of the path
+    #[error("path mismatch: {path:?} at segment {index}")]
+    PathMismatch {
+        /// concrete path
+        path: String,
+        /// zero-based index of the item that did not match
+        index: usize,
+    },
+    /// the parameters of an alias differ from the ones of the path of its route
+    #[error("alias parameter mismatch: {alias:?}")]
+    AliasParameterMismatch {
+        /// alias with different parameters
+        alias: String,
+    },
 }
 
 #[cfg(test)]
 mod tests {
 
     use crate::map::Map;
-    use crate::{path::Path, PathError};
+    use crate::{path::Path, validate_path, PathError};
     use http::Method;
     use rstest::*;
+    use std::convert::TryFrom;
 
-    use super::Item;
+    use super::{Item, WILDCARD_PARAMETER};
 
     #[rstest(
         input,
         result,
         case("/", true),
         case("", true),
-        case("/*foo/asdf", false)
+        case("/*foo/asdf", false),
+        case("/static/*", true),
+        case("/users/:", false),
+        case("/users/:?", false),
+        case("/ü/foo", true),
+        case("/日本/:id", true)
     )]
     fn test_parse(input: &str, result: bool) {
         assert_eq!(Path::parse(Method::GET, input).is_ok(), result);
+        assert_eq!(validate_path(input).is_ok(), result);
+    }
+
+    #[rstest]
+    fn test_parse_multi_byte_static() {
+        let path = Path::parse_get("/ü/foo").unwrap();
+
+        assert!(path.get_items()[0].is_static());
+        assert_eq!(path.get_items()[0].get_name(), "ü");
+        assert_eq!(path.render_original(), "ü/foo");
+    }
+
+    #[rstest]
+    fn test_escaped_static() {
+        let path = Path::parse_get("/api/\\:version/\\*/:id").unwrap();
+        let items = path.get_items();
+
+        assert!(items[1].is_static());
+        assert_eq!(items[1].get_name(), ":version");
+        assert!(items[2].is_static());
+        assert_eq!(items[2].get_name(), "*");
+        assert!(items[3].is_parameter());
+        assert_eq!(path.render_original(), "api/\\:version/\\*/:id");
+        assert_eq!(Path::parse_get(&path.render_original()).unwrap(), path);
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+        assert_eq!(path.render(params).unwrap(), "/api/:version/*/5");
+        assert_eq!(
+            path.extract("/api/:version/*/5")
+                .unwrap()
+                .get("id")
+                .unwrap(),
+            "5"
+        );
+        assert!(Path::parse_get("/\\foo").unwrap().get_items()[0].is_static());
+        assert_eq!(
+            Path::parse_get("/\\foo").unwrap().get_items()[0].get_name(),
+            "\\foo"
+        );
     }
 
     #[rstest]
@@ -186,6 +774,307 @@ mod tests {
         );
     }
 
+    #[rstest(
+        input,
+        name,
+        case("/users/:id/posts/:id", "id"),
+        case("/users/:id/files/*id", "id"),
+        case("/users/:id(\\d+)/posts/:id?", "id")
+    )]
+    fn test_duplicate_parameter_name(input: &str, name: &str) {
+        assert_eq!(
+            Path::parse(Method::GET, input).unwrap_err(),
+            PathError::DuplicateParameterName {
+                name: String::from(name)
+            }
+        );
+    }
+
+    #[rstest(
+        input,
+        name,
+        case("/users/:user id", "user id"),
+        case("/users/:{id}", "{id}"),
+        case("/users/:id.json", "id.json"),
+        case("/files/*a b", "a b"),
+        case("/users/:na%me?", "na%me")
+    )]
+    fn test_invalid_parameter_name(input: &str, name: &str) {
+        assert_eq!(
+            Path::parse(Method::GET, input).unwrap_err(),
+            PathError::InvalidParameterName {
+                name: String::from(name)
+            }
+        );
+        assert!(Path::parse(Method::GET, "/users/:user_id/:post-id(\\d+)/*").is_ok());
+    }
+
+    #[rstest(
+        pattern,
+        input,
+        expected,
+        case("/users/:id/posts/:post", "/users/42/posts/7", Some(&[("id", "42"), ("post", "7")][..])),
+        case("/users/:id(\\d+)", "/users/42", Some(&[("id", "42")][..])),
+        case("/users/:id(\\d+)", "/users/abc", None),
+        case("/users/:id", "/posts/42", None),
+        case("/users/:id", "/users/42/posts", None),
+        case("/users/:id", "/users", None),
+        case("/search/:query?", "/search", Some(&[][..])),
+        case("/search/:query?", "/search/rust", Some(&[("query", "rust")][..])),
+        case("/files/*path", "/files/a/b.txt", Some(&[("path", "a/b.txt")][..])),
+        case("/files/*path", "/files/", Some(&[("path", "")][..])),
+        case("/files/*path", "/files", Some(&[("path", "")][..])),
+        case("/files/*path", "/file", None),
+        case("/static/*", "/static/css/main.css", Some(&[(WILDCARD_PARAMETER, "css/main.css")][..]))
+    )]
+    fn test_extract(pattern: &str, input: &str, expected: Option<&[(&str, &str)]>) {
+        let result = Path::parse(Method::GET, pattern).unwrap().extract(input);
+
+        match expected {
+            Option::Some(expected) => {
+                let params = result.unwrap();
+                assert_eq!(params.len(), expected.len());
+                for (name, value) in expected {
+                    assert_eq!(params.get(*name).unwrap(), value);
+                }
+            }
+            Option::None => match result.unwrap_err() {
+                PathError::PathMismatch { path, .. } => assert_eq!(path, input),
+                err => panic!("unexpected error: {:?}", err),
+            },
+        }
+    }
+
+    #[rstest]
+    fn test_extract_greedy() {
+        let mut path = Path::parse(Method::GET, "/docs/:version").unwrap();
+        assert!(path.extract("/docs/v1/intro").is_err());
+
+        path.set_greedy(true);
+        assert_eq!(
+            path.extract("/docs/v1/intro")
+                .unwrap()
+                .get("version")
+                .unwrap(),
+            "v1"
+        );
+    }
+
+    #[rstest]
+    fn test_eq() {
+        let path = Path::parse(Method::GET, "/users/:id(\\d+)").unwrap();
+
+        assert_eq!(path, Path::parse(Method::GET, "users/:id(\\d+)/").unwrap());
+        assert_ne!(path, Path::parse(Method::POST, "/users/:id(\\d+)").unwrap());
+        assert_ne!(path, Path::parse(Method::GET, "/users/:id").unwrap());
+        assert_ne!(path, Path::parse(Method::GET, "/users/:id(\\d+)i").unwrap());
+
+        let mut greedy = path.clone();
+        greedy.set_greedy(true);
+        assert_ne!(path, greedy);
+    }
+
+    #[rstest]
+    fn test_from_str() {
+        let path: Path = "/users/:id".parse().unwrap();
+
+        assert_eq!(path.get_method(), &Method::GET);
+        assert_eq!(path, Path::parse(Method::GET, "/users/:id").unwrap());
+        assert_eq!(path, Path::parse_get("/users/:id").unwrap());
+        assert_eq!(
+            "/files/*a/b".parse::<Path>().unwrap_err(),
+            PathError::WildcardItemMustBeLast
+        );
+    }
+
+    #[rstest]
+    fn test_try_from() {
+        let path = Path::try_from("POST  /users/:id").unwrap();
+
+        assert_eq!(path, Path::parse(Method::POST, "/users/:id").unwrap());
+        assert_eq!(Path::try_from(path.to_string().as_str()).unwrap(), path);
+        assert!(Path::try_from("GET /").unwrap().is_root());
+        assert_eq!(
+            Path::try_from("/users").unwrap_err(),
+            PathError::InvalidMethod {
+                method: String::from("/users")
+            }
+        );
+        assert_eq!(
+            Path::try_from("GET /files/*a/b").unwrap_err(),
+            PathError::WildcardItemMustBeLast
+        );
+    }
+
+    #[rstest(
+        input,
+        open,
+        close,
+        expected,
+        case("/users/:id/posts/:post", "{", "}", "/users/{id}/posts/{post}"),
+        case("/users/:id(\\d+)/:tab?", "{", "}", "/users/{id}/{tab}"),
+        case("/files/*path", "<", ">", "/files/<path>"),
+        case("/", "{", "}", "/")
+    )]
+    fn test_render_template(input: &str, open: &str, close: &str, expected: &str) {
+        assert_eq!(
+            Path::parse_get(input).unwrap().render_template(open, close),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_item_accessors() {
+        let path = Path::parse_get("/users/:id(\\d+)/\\:raw/*rest").unwrap();
+        let kinds: Vec<(&str, &str, bool, bool, bool, bool)> = path
+            .get_items()
+            .iter()
+            .map(|item: &crate::Item| {
+                (
+                    item.get_name(),
+                    item.get_parameter_name(),
+                    item.is_static(),
+                    item.is_parameter(),
+                    item.is_optional_parameter(),
+                    item.is_wildcard(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("users", "users", true, false, false, false),
+                (":id(\\d+)", "id", false, true, false, false),
+                (":raw", ":raw", true, false, false, false),
+                ("*rest", "rest", false, false, false, true),
+            ]
+        );
+        assert_eq!(path.get_items()[0], Item::Static(String::from("users")));
+
+        let optional = Path::parse_get("/users/:tab?").unwrap().get_items()[1].clone();
+        assert!(optional.is_optional_parameter() && !optional.is_parameter());
+        assert_eq!(optional.get_parameter_name(), "tab");
+    }
+
+    #[rstest(
+        item,
+        name,
+        case(Item::Parameter(String::new()), ""),
+        case(Item::OptionalParameter(String::new()), ""),
+        case(Item::Wildcard(String::new()), ""),
+        case(Item::Parameter(String::from("éa")), "éa"),
+        case(Item::OptionalParameter(String::from("é")), "é"),
+        case(Item::Parameter(String::from(":é")), "é")
+    )]
+    fn test_item_built_by_hand(item: Item, name: &str) {
+        assert_eq!(item.get_parameter_name(), name);
+        assert!(item.validate().is_err());
+        assert!(item.get_constraint().unwrap().is_none());
+    }
+
+    #[rstest]
+    fn test_display() {
+        let path = Path::parse(Method::POST, "/users/:id(\\d+)/*").unwrap();
+        assert_eq!(path.to_string(), "POST /users/:id(\\d+)/*");
+        assert_eq!(Path::parse(Method::GET, "/").unwrap().to_string(), "GET /");
+    }
+
+    #[rstest]
+    fn test_optional_must_be_last() {
+        assert_eq!(
+            Path::parse(Method::GET, "/foo/:bar?/baz").unwrap_err(),
+            PathError::OptionalItemMustBeLast
+        );
+        assert!(
+            Path::parse(Method::GET, "/foo/:bar?").unwrap().get_items()[1].is_optional_parameter()
+        );
+    }
+
+    #[rstest(
+        input,
+        result,
+        case("/:status(active|inactive)", true),
+        case("/:status(active|inactive)i", true),
+        case("/:status(active|inactive)i?", true),
+        case("/:status(active|inactive", false),
+        case("/:status(active)x", false),
+        case("/:id(\\d+)", true),
+        case("/:id([0-9)", false),
+        case("/files/*path(\\.txt)", false)
+    )]
+    fn test_parse_constraint(input: &str, result: bool) {
+        assert_eq!(Path::parse(Method::GET, input).is_ok(), result);
+    }
+
+    #[rstest]
+    fn test_constraint() {
+        let item = Item::Parameter(String::from(":status(active|inactive)i"));
+        assert_eq!(item.get_parameter_name(), "status");
+
+        let constraint = item.get_constraint().unwrap().unwrap();
+        assert!(constraint.matches("active"));
+        assert!(constraint.matches("Active"));
+        assert!(!constraint.matches("deleted"));
+
+        let constraint = Item::Parameter(String::from(":status(active|inactive)"))
+            .get_constraint()
+            .unwrap()
+            .unwrap();
+        assert!(constraint.matches("active"));
+        assert!(!constraint.matches("Active"));
+
+        let constraint = Item::Parameter(String::from(":id(\\d+)"))
+            .get_constraint()
+            .unwrap()
+            .unwrap();
+        assert!(constraint.matches("123"));
+        assert!(!constraint.matches("abc"));
+        assert!(!constraint.matches("123abc"));
+    }
+
+    #[rstest]
+    fn test_invalid_constraint() {
+        assert_eq!(
+            Path::parse(Method::GET, "/users/:id([0-9)").unwrap_err(),
+            PathError::InvalidConstraint {
+                parameter: String::from(":id([0-9)")
+            }
+        );
+        assert_eq!(
+            Path::parse(Method::GET, "/files/*path(\\.txt)").unwrap_err(),
+            PathError::InvalidConstraint {
+                parameter: String::from("*path(\\.txt)")
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_with_prefix() {
+        let path = Path::parse(Method::POST, "/users/:id").unwrap();
+
+        let prefix = Path::parse_prefix("/api/:version").unwrap();
+        let prefixed = path.with_prefix(&prefix).unwrap();
+        assert_eq!(prefixed.render_original(), "api/:version/users/:id");
+        assert_eq!(prefixed.get_method(), &Method::POST);
+        assert_eq!(prefixed.get_constraints().len(), 4);
+        assert_eq!(
+            path.with_prefix(&Path::parse_prefix("/:id").unwrap())
+                .unwrap_err(),
+            PathError::DuplicateParameterName {
+                name: String::from("id")
+            }
+        );
+        assert_eq!(
+            Path::parse_prefix("/files/*rest").unwrap_err(),
+            PathError::WildcardItemMustBeLast
+        );
+        assert_eq!(
+            Path::parse_prefix("/files/:name?").unwrap_err(),
+            PathError::OptionalItemMustBeLast
+        );
+    }
+
     #[rstest]
     fn test_missing_params() {
         let path = Path::parse(Method::GET, "/foo/:bar/baz/*asdf").unwrap();
@@ -200,6 +1089,125 @@ mod tests {
         }
     }
 
+    #[rstest]
+    fn test_unnamed_wildcard() {
+        let path = Path::parse(Method::GET, "/static/*").unwrap();
+        assert_eq!(path.get_items()[1].get_parameter_name(), WILDCARD_PARAMETER);
+
+        let mut params = Map::new();
+        params.insert(
+            String::from(WILDCARD_PARAMETER),
+            String::from("css/main.css"),
+        );
+        assert_eq!(path.render(params).unwrap(), "/static/css/main.css");
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("a/b/c", "/files/a/b/c"),
+        case("/a/b/c/", "/files/a/b/c"),
+        case("//", "/files"),
+        case("", "/files")
+    )]
+    fn test_render_wildcard(value: &str, expected: &str) {
+        let mut params = Map::new();
+        params.insert(String::from("path"), String::from(value));
+
+        assert_eq!(
+            Path::parse_get("/files/*path")
+                .unwrap()
+                .render(params)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_render_strict() {
+        let path = Path::parse(Method::GET, "/users/:id/:tab?").unwrap();
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+        assert_eq!(path.render_strict(params.clone()).unwrap(), "/users/5");
+
+        params.insert(String::from("tab"), String::from("posts"));
+        assert_eq!(
+            path.render_strict(params.clone()).unwrap(),
+            "/users/5/posts"
+        );
+
+        params.insert(String::from("sort"), String::from("name"));
+        params.insert(String::from("page"), String::from("2"));
+        assert_eq!(path.render(params.clone()).unwrap(), "/users/5/posts");
+        assert_eq!(
+            path.render_strict(params).unwrap_err(),
+            PathError::UnusedParameter {
+                parameter: String::from("page")
+            }
+        );
+
+        let root = Path::parse(Method::GET, "/").unwrap();
+        assert_eq!(root.render(Map::new()).unwrap(), "/");
+    }
+
+    #[rstest]
+    fn test_root_and_empty_path() {
+        let root = Path::parse_get("/").unwrap();
+        let empty = Path::parse_get("").unwrap();
+
+        assert!(root.is_root());
+        assert!(!empty.is_root());
+        assert!(root.is_empty() && empty.is_empty());
+        assert_ne!(root, empty);
+        assert_eq!(root.render_original(), "/");
+        assert_eq!(empty.render_original(), "");
+        assert_eq!(root.to_string(), "GET /");
+        assert_eq!(Path::parse_get(&root.render_original()).unwrap(), root);
+        assert!(!Path::parse_get("/users").unwrap().is_root());
+    }
+
+    #[rstest]
+    fn test_render_optional() {
+        let path = Path::parse(Method::GET, "/users/:id/:tab?").unwrap();
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+        assert_eq!(path.render(params.clone()).unwrap(), "/users/5");
+
+        params.insert(String::from("tab"), String::from("profile"));
+        assert_eq!(path.render(params).unwrap(), "/users/5/profile");
+
+        assert_eq!(
+            path.render(Map::new()).unwrap_err(),
+            PathError::ParameterNotFound {
+                parameter: String::from(":id"),
+                index: 1
+            }
+        );
+    }
+
+    #[rstest(
+        input,
+        index,
+        case("/users/42/posts/abc", 3),
+        case("/users/42/comments/7", 2),
+        case("/users/42/posts", 3),
+        case("/users/42/posts/7/edit", 4),
+        case("/posts", 0)
+    )]
+    fn test_extract_mismatch_index(input: &str, index: usize) {
+        let path = Path::parse_get("/users/:id/posts/:post(\\d+)").unwrap();
+
+        assert_eq!(
+            path.extract(input).unwrap_err(),
+            PathError::PathMismatch {
+                path: String::from(input),
+                index
+            }
+        );
+    }
+
     #[rstest]
     fn test_deal_with_empty_path_items() {
         let path = Path::parse(Method::GET, "//").unwrap();
@@ -231,6 +1239,23 @@ mod tests {
         assert!(wildcard_item.is_wildcard());
     }
 
+    #[rstest(
+        input,
+        expected,
+        case("/", 0),
+        case("/users", 3),
+        case("/users/:id", 5),
+        case("/users/:id(\\d+)", 5),
+        case("/users/:id/posts", 8),
+        case("/users/:tab?", 5),
+        case("/users/*path", 4),
+        case("/*", 1),
+        case("/:section/*path", 3)
+    )]
+    fn test_specificity(input: &str, expected: u32) {
+        assert_eq!(Path::parse_get(input).unwrap().specificity(), expected);
+    }
+
     #[rstest]
     fn test_item_returns_method() {
         let path = Path::parse(Method::OPTIONS, "/").unwrap();
@@ -0,0 +1,82 @@
+use http::header::CONTENT_TYPE;
+use http::HeaderMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// The parts of a request the route predicates are evaluated against, see
+/// [`Route::with_predicate`](struct.Route.html#method.with_predicate).
+#[derive(Debug, Clone, Copy)]
+pub struct PredicateContext<'a> {
+    headers: &'a HeaderMap,
+}
+
+impl<'a> PredicateContext<'a> {
+    /// Create a context from the headers of a request.
+    pub fn new(headers: &'a HeaderMap) -> Self {
+        PredicateContext { headers }
+    }
+
+    /// Return the headers of the request.
+    pub fn get_headers(&self) -> &'a HeaderMap {
+        self.headers
+    }
+
+    /// Return the media type of the `Content-Type` header without its parameters, e.g.
+    /// `application/json` for `application/json; charset=utf-8`.
+    ///
+    /// A missing or non-ASCII header is returned as `None`.
+    pub fn content_type(&self) -> Option<&'a str> {
+        self.headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(str::trim)
+    }
+}
+
+/// A shared route predicate, cloned with the route.
+#[derive(Clone)]
+pub(crate) struct RoutePredicate(Arc<dyn Fn(&PredicateContext<'_>) -> bool + Send + Sync>);
+
+impl RoutePredicate {
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&PredicateContext<'_>) -> bool + Send + Sync + 'static,
+    {
+        RoutePredicate(Arc::new(predicate))
+    }
+
+    pub fn accepts(&self, context: &PredicateContext<'_>) -> bool {
+        (self.0)(context)
+    }
+}
+
+impl fmt::Debug for RoutePredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RoutePredicate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::PredicateContext;
+    use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+    use rstest::*;
+
+    #[rstest(
+        value,
+        expected,
+        case(Some("application/json"), Some("application/json")),
+        case(Some("text/html; charset=utf-8"), Some("text/html")),
+        case(None, None)
+    )]
+    fn test_content_type(value: Option<&str>, expected: Option<&str>) {
+        let mut headers = HeaderMap::new();
+        if let Option::Some(value) = value {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_str(value).unwrap());
+        }
+
+        assert_eq!(PredicateContext::new(&headers).content_type(), expected);
+    }
+}
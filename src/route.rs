@@ -1,25 +1,225 @@
+use crate::map::Map;
 use crate::path::Path;
+use crate::predicate::RoutePredicate;
 use crate::PathError;
+use crate::PredicateContext;
 use http::Method;
+#[cfg(feature = "serde")]
+use serde::{de::Error, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Route metadata map.
+pub type RouteMeta = Map<String, String>;
+
+/// Returns the names of the parameters and wildcards of a path, sorted.
+fn parameter_names(path: &Path) -> Vec<&str> {
+    let mut names: Vec<&str> = path
+        .get_items()
+        .iter()
+        .filter(|item| !item.is_static())
+        .map(|item| item.get_parameter_name())
+        .collect();
+    names.sort_unstable();
+
+    names
+}
 
 /// A single route.
 ///
-/// It contains the name of the route, the route's path and an item that is held inside.
+/// It contains the name of the route, the route's path and aliases, an item that is held inside
+/// and optional metadata.
 #[derive(Debug, Clone)]
 pub struct Route<T> {
     name: String,
     path: Path,
+    pattern: String,
+    aliases: Vec<Path>,
     item: T,
+    meta: RouteMeta,
+    predicate: Option<RoutePredicate>,
 }
 
 impl<T> Route<T> {
     /// Create a new route.
     pub fn create(name: &str, method: Method, path: &str, item: T) -> Result<Self, PathError> {
-        Result::Ok(Route {
+        Result::Ok(Route::from_path(name, Path::parse(method, path)?, item))
+    }
+
+    /// Create a new route from an already parsed path.
+    ///
+    /// This allows parsing a path once and creating several routes with it.
+    pub fn from_path(name: &str, path: Path, item: T) -> Self {
+        Route {
             name: String::from(name),
-            path: Path::parse(method, path)?,
+            pattern: path.render_absolute(),
+            path,
+            aliases: Vec::new(),
             item,
-        })
+            meta: RouteMeta::new(),
+            predicate: Option::None,
+        }
+    }
+
+    /// Create a new greedy route.
+    ///
+    /// A greedy route also matches requests that continue past its last segment. The
+    /// unconsumed part of the request path is available through
+    /// [`RouteMatch::remainder`](struct.RouteMatch.html#method.remainder).
+    pub fn create_greedy(
+        name: &str,
+        method: Method,
+        path: &str,
+        item: T,
+    ) -> Result<Self, PathError> {
+        let mut route = Self::create(name, method, path, item)?;
+        route.path.set_greedy(true);
+
+        Result::Ok(route)
+    }
+
+    /// Create a route for each of the given methods with the same path and item.
+    ///
+    /// The names of the routes are suffixed with the lowercase method, e.g. `search.get` and
+    /// `search.post`, since route names must be unique.
+    pub fn create_multi(
+        name: &str,
+        methods: &[Method],
+        path: &str,
+        item: T,
+    ) -> Result<Vec<Self>, PathError>
+    where
+        T: Clone,
+    {
+        methods
+            .iter()
+            .map(|method| {
+                let name = format!("{}.{}", name, method.as_str().to_lowercase());
+                Self::create(&name, method.clone(), path, item.clone())
+            })
+            .collect()
+    }
+
+    /// Return the route with a prefix prepended to its path and a new name.
+    pub(crate) fn with_prefix(self, name: String, prefix: &Path) -> Result<Self, PathError> {
+        let mut route = Route::from_path(&name, self.path.with_prefix(prefix)?, self.item)
+            .with_meta_map(self.meta);
+        route.aliases = self
+            .aliases
+            .iter()
+            .map(|alias| alias.with_prefix(prefix))
+            .collect::<Result<_, _>>()?;
+        route.predicate = self.predicate;
+
+        Result::Ok(route)
+    }
+
+    /// Return the route with its item transformed, keeping its name, path, metadata and
+    /// predicate.
+    pub fn map_item<U, F>(self, f: F) -> Route<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Route {
+            name: self.name,
+            path: self.path,
+            pattern: self.pattern,
+            aliases: self.aliases,
+            item: f(self.item),
+            meta: self.meta,
+            predicate: self.predicate,
+        }
+    }
+
+    /// Attach a metadata entry to the route, like an auth requirement or a rate limit tier.
+    ///
+    /// The metadata of the matched route is available through
+    /// [`RouteMatch::get_meta`](struct.RouteMatch.html#method.get_meta).
+    pub fn with_meta(mut self, key: &str, value: &str) -> Self {
+        self.meta.insert(String::from(key), String::from(value));
+        self
+    }
+
+    fn with_meta_map(mut self, meta: RouteMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Set the priority of the route, 0 by default.
+    ///
+    /// When the parameters or wildcards of several routes accept a segment, the matching route
+    /// with the higher priority wins, then the ones with constraints, then the ones without,
+    /// and wildcards last. Static segments always take precedence.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.path.set_priority(priority);
+        for alias in &mut self.aliases {
+            alias.set_priority(priority);
+        }
+        self
+    }
+
+    /// Add another path the route is reachable at, e.g. `/u/:id` for `/users/:id`.
+    ///
+    /// The alias has the method, the greediness and the priority of the route, and it is
+    /// registered in the router under the same name, so a conflict with it is handled like a
+    /// conflict with the path of the route. Links are rendered from the path of the route, and
+    /// the matches report its pattern whichever path was resolved. A route with aliases cannot
+    /// have a predicate.
+    ///
+    /// The alias must have the parameters of the path of the route, so the matches capture the
+    /// same parameters whichever path was resolved.
+    pub fn with_alias(mut self, path: &str) -> Result<Self, PathError> {
+        let mut alias = Path::parse(self.path.get_method().clone(), path)?;
+        if parameter_names(&alias) != parameter_names(&self.path) {
+            return Result::Err(PathError::AliasParameterMismatch {
+                alias: String::from(path),
+            });
+        }
+        alias.set_greedy(self.path.is_greedy());
+        alias.set_priority(self.path.get_priority());
+        self.aliases.push(alias);
+
+        Result::Ok(self)
+    }
+
+    /// Return the aliases of the route, see [`with_alias`](#method.with_alias).
+    pub fn get_aliases(&self) -> &[Path] {
+        &self.aliases
+    }
+
+    /// Return the path of the route followed by its aliases.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(&self.path).chain(self.aliases.iter())
+    }
+
+    /// Set a predicate the request must satisfy besides the method and the path, e.g. on its
+    /// `Content-Type`.
+    ///
+    /// Several routes may share a method and a path when all but at most one of them have a
+    /// predicate. They are tried in the order they were added, and the route without a
+    /// predicate is used when none of the predicates pass. The predicates are only evaluated by
+    /// [`Router::resolve_with`](struct.Router.html#method.resolve_with), the other lookups
+    /// skip the routes with a predicate.
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&PredicateContext<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Option::Some(RoutePredicate::new(predicate));
+        self
+    }
+
+    /// Return true if the route has a predicate.
+    pub fn has_predicate(&self) -> bool {
+        self.predicate.is_some()
+    }
+
+    /// Whether the route accepts a request with the given context, always true without a
+    /// predicate and always false without a context.
+    pub(crate) fn accepts(&self, context: Option<&PredicateContext<'_>>) -> bool {
+        match (&self.predicate, context) {
+            (Option::None, _) => true,
+            (Option::Some(predicate), Option::Some(context)) => predicate.accepts(context),
+            (Option::Some(_), Option::None) => false,
+        }
     }
 
     /// Return the name of the route.
@@ -32,16 +232,136 @@ impl<T> Route<T> {
         &self.path
     }
 
+    /// Return the original path of the route with a leading slash, e.g. `/users/:id`.
+    ///
+    /// It is rendered once when the route is created, so it can be borrowed by every match.
+    pub fn get_pattern(&self) -> &str {
+        &self.pattern
+    }
+
     /// Return the item of the route.
     pub fn get_item(&self) -> &T {
         &self.item
     }
+
+    /// Return the metadata of the route.
+    pub fn get_meta(&self) -> &RouteMeta {
+        &self.meta
+    }
+
+    pub(crate) fn get_item_mut(&mut self) -> &mut T {
+        &mut self.item
+    }
+}
+
+/// Routes are displayed with their name and path, e.g. `users.show (GET /users/:id)`.
+impl<T> fmt::Display for Route<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.path)
+    }
+}
+
+/// Routes are serialized with their name, method, original path, aliases, greediness, priority,
+/// item and metadata. The default priority, empty aliases and empty metadata are left out.
+/// Predicates cannot be serialized, they are left out as well.
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for Route<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Route", 8)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("method", self.path.get_method().as_str())?;
+        state.serialize_field("path", &self.path.render_absolute())?;
+        if self.aliases.is_empty() {
+            state.skip_field("aliases")?;
+        } else {
+            let aliases: Vec<String> = self.aliases.iter().map(Path::render_absolute).collect();
+            state.serialize_field("aliases", &aliases)?;
+        }
+        state.serialize_field("greedy", &self.path.is_greedy())?;
+        if self.path.get_priority() == 0 {
+            state.skip_field("priority")?;
+        } else {
+            state.serialize_field("priority", &self.path.get_priority())?;
+        }
+        state.serialize_field("item", &self.item)?;
+        if self.meta.is_empty() {
+            state.skip_field("meta")?;
+        } else {
+            state.serialize_field("meta", &self.meta)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RouteDefinition<T> {
+    name: String,
+    method: String,
+    path: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    greedy: bool,
+    #[serde(default)]
+    priority: i32,
+    item: T,
+    #[serde(default)]
+    meta: RouteMeta,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Route<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let definition = RouteDefinition::deserialize(deserializer)?;
+        let method = Method::from_bytes(definition.method.as_bytes()).map_err(D::Error::custom)?;
+        let create = if definition.greedy {
+            Route::create_greedy
+        } else {
+            Route::create
+        };
+
+        let meta = definition.meta;
+        let priority = definition.priority;
+        let aliases = definition.aliases;
+
+        create(&definition.name, method, &definition.path, definition.item)
+            .and_then(|route| {
+                aliases
+                    .iter()
+                    .try_fold(route, |route, alias| route.with_alias(alias))
+            })
+            .map(|mut route| {
+                route.meta = meta;
+                route.with_priority(priority)
+            })
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Create a [`Route`](struct.Route.html) with the method given as an identifier.
+///
+/// The method must be one of the constants of `http::Method`, so a typo in it fails to compile.
+/// The path is parsed like in [`Route::create`](struct.Route.html#method.create), and the
+/// result is returned the same way.
+///
+/// ```
+/// use star_router::route;
+///
+/// let route = route!(GET, "users.show", "/users/:id", 0).unwrap();
+/// assert_eq!(route.to_string(), "users.show (GET /users/:id)");
+/// ```
+#[macro_export]
+macro_rules! route {
+    ($method:ident, $name:expr, $path:literal, $item:expr $(,)?) => {
+        $crate::Route::create($name, $crate::__http::Method::$method, $path, $item)
+    };
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::Route;
+    use crate::{Path, PathError, Route};
     use http::Method;
     use rstest::*;
 
@@ -56,4 +376,200 @@ mod tests {
         let item = 0;
         assert_eq!(Route::create(name, method, path, item).is_ok(), result);
     }
+
+    #[rstest]
+    fn test_from_path() {
+        let path = Path::parse(Method::GET, "/experiment/:id").unwrap();
+        let a = Route::from_path("experiment.a", path.clone(), 1);
+        let b = Route::from_path("experiment.b", path.clone(), 2);
+
+        assert_eq!(a.get_name(), "experiment.a");
+        assert_eq!(a.get_path(), &path);
+        assert_eq!(b.get_path(), &path);
+        assert_eq!(b.get_item(), &2);
+    }
+
+    #[rstest]
+    fn test_create_multi() {
+        let routes =
+            Route::create_multi("search", &[Method::GET, Method::POST], "/search", 0).unwrap();
+
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].get_name(), "search.get");
+        assert_eq!(routes[0].get_path().get_method(), &Method::GET);
+        assert_eq!(routes[1].get_name(), "search.post");
+        assert_eq!(routes[1].get_path().get_method(), &Method::POST);
+        assert!(Route::create_multi("search", &[Method::GET], "/*a/b", 0).is_err());
+    }
+
+    #[rstest]
+    fn test_create_greedy() {
+        assert!(
+            Route::create_greedy("files", Method::GET, "/files/:name", 0)
+                .unwrap()
+                .get_path()
+                .is_greedy()
+        );
+        assert!(!Route::create("files", Method::GET, "/files/:name", 0)
+            .unwrap()
+            .get_path()
+            .is_greedy());
+    }
+
+    #[rstest]
+    fn test_display() {
+        let route = Route::create("users.show", Method::GET, "/users/:id", 0).unwrap();
+        assert_eq!(route.to_string(), "users.show (GET /users/:id)");
+    }
+
+    #[rstest]
+    fn test_route_macro() {
+        let route = route!(POST, "users.create", "/users", 1).unwrap();
+        assert_eq!(route.get_name(), "users.create");
+        assert_eq!(route.get_path().get_method(), &Method::POST);
+        assert_eq!(route.get_item(), &1);
+        assert!(route!(GET, "invalid", "/*rest/foo", 0).is_err());
+    }
+
+    #[rstest]
+    fn test_pattern() {
+        let route = Route::create("users.show", Method::GET, "/users/:id", 0).unwrap();
+        assert_eq!(route.get_pattern(), "/users/:id");
+
+        let prefix = Path::parse_prefix("/api").unwrap();
+        let route = route
+            .with_prefix(String::from("api.users.show"), &prefix)
+            .unwrap();
+        assert_eq!(route.get_pattern(), "/api/users/:id");
+        assert_eq!(
+            Route::create("root", Method::GET, "/", 0)
+                .unwrap()
+                .get_pattern(),
+            "/"
+        );
+    }
+
+    #[rstest]
+    fn test_meta() {
+        let route = Route::create("admin", Method::GET, "/admin", 0)
+            .unwrap()
+            .with_meta("auth", "admin")
+            .with_meta("rate_limit", "low");
+
+        assert_eq!(route.get_meta().get("auth").unwrap(), "admin");
+        assert_eq!(route.get_meta().get("rate_limit").unwrap(), "low");
+        assert!(Route::create("index", Method::GET, "/", 0)
+            .unwrap()
+            .get_meta()
+            .is_empty());
+    }
+
+    #[rstest]
+    fn test_with_alias() {
+        let route = Route::create_greedy("files", Method::GET, "/files", 1)
+            .unwrap()
+            .with_alias("/f")
+            .unwrap()
+            .with_priority(2);
+
+        let alias = &route.get_aliases()[0];
+        assert_eq!(alias.to_string(), "GET /f");
+        assert!(alias.is_greedy());
+        assert_eq!(alias.get_priority(), 2);
+        assert_eq!(route.get_pattern(), "/files");
+        assert!(route.clone().with_alias("/*rest/f").is_err());
+        assert_eq!(
+            route.clone().with_alias("/f/:id").unwrap_err(),
+            PathError::AliasParameterMismatch {
+                alias: String::from("/f/:id"),
+            }
+        );
+        let user = Route::create("user", Method::GET, "/users/:id/posts/:post", 1).unwrap();
+        assert!(user.clone().with_alias("/p/:post/u/:id").is_ok());
+        assert_eq!(
+            user.with_alias("/u/:uid/posts/:post").unwrap_err(),
+            PathError::AliasParameterMismatch {
+                alias: String::from("/u/:uid/posts/:post"),
+            }
+        );
+
+        let prefix = Path::parse_prefix("/api").unwrap();
+        let route = route
+            .with_prefix(String::from("api.files"), &prefix)
+            .unwrap();
+        assert_eq!(route.get_aliases()[0].to_string(), "GET /api/f");
+    }
+
+    #[rstest]
+    fn test_map_item() {
+        let route = Route::create("user", Method::GET, "/users/:id", 5)
+            .unwrap()
+            .with_meta("auth", "user")
+            .map_item(|id| id.to_string());
+
+        assert_eq!(route.get_item(), "5");
+        assert_eq!(route.get_name(), "user");
+        assert_eq!(route.get_pattern(), "/users/:id");
+        assert_eq!(route.get_meta().get("auth").unwrap(), "user");
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_serde() {
+        let route = Route::create_greedy("files", Method::GET, "/files/:name", 5).unwrap();
+
+        let encoded = serde_json::to_string(&route).unwrap();
+        assert_eq!(
+            encoded,
+            r#"{"name":"files","method":"GET","path":"/files/:name","greedy":true,"item":5}"#
+        );
+
+        let decoded: Route<u64> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.get_name(), "files");
+        assert!(decoded.get_path().is_greedy());
+        assert_eq!(decoded.get_item(), &5);
+
+        let decoded: Route<u64> =
+            serde_json::from_str(r#"{"name":"a","method":"POST","path":"/a","item":1}"#).unwrap();
+        assert_eq!(decoded.get_path().get_method(), &Method::POST);
+        assert!(!decoded.get_path().is_greedy());
+        assert!(serde_json::from_str::<Route<u64>>(
+            r#"{"name":"a","method":"GET","path":"/*a/b","item":1}"#
+        )
+        .is_err());
+
+        let route = Route::create("a", Method::GET, "/a", 1)
+            .unwrap()
+            .with_meta("auth", "user");
+        let encoded = serde_json::to_string(&route).unwrap();
+        assert_eq!(
+            encoded,
+            r#"{"name":"a","method":"GET","path":"/a","greedy":false,"item":1,"meta":{"auth":"user"}}"#
+        );
+        let decoded: Route<u64> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.get_meta().get("auth").unwrap(), "user");
+
+        let route = Route::create("a", Method::GET, "/a", 1)
+            .unwrap()
+            .with_priority(3);
+        let encoded = serde_json::to_string(&route).unwrap();
+        assert_eq!(
+            encoded,
+            r#"{"name":"a","method":"GET","path":"/a","greedy":false,"priority":3,"item":1}"#
+        );
+        let decoded: Route<u64> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.get_path().get_priority(), 3);
+
+        let route = Route::create("user", Method::GET, "/users/:id", 1)
+            .unwrap()
+            .with_alias("/u/:id")
+            .unwrap();
+        let encoded = serde_json::to_string(&route).unwrap();
+        assert_eq!(
+            encoded,
+            r#"{"name":"user","method":"GET","path":"/users/:id","aliases":["/u/:id"],"greedy":false,"item":1}"#
+        );
+        let decoded: Route<u64> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.get_aliases(), route.get_aliases());
+    }
 }
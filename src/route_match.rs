@@ -1,24 +1,149 @@
 use crate::map::Map;
+use crate::path::WILDCARD_PARAMETER;
+use crate::route::RouteMeta;
+use http::Extensions;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
 
 /// Route parameter map.
+///
+/// The keys are the parameter names of the registered routes, not parts of the request, so the
+/// map keeps the default FNV hasher.
 pub type RouteParameter = Map<String, String>;
 
+/// Typed accessors of the route parameters.
+///
+/// Bring it into scope to read numeric ids without repeating the parsing in every handler.
+/// Other types implementing [`FromStr`](https://doc.rust-lang.org/std/str/trait.FromStr.html),
+/// like UUIDs, can be read with [`get_parsed`](#tymethod.get_parsed).
+pub trait RouteParameterExt {
+    /// Return a parameter parsed into the given type.
+    ///
+    /// Missing and unparseable parameters are both returned as `None`.
+    fn get_parsed<P: FromStr>(&self, key: &str) -> Option<P>;
+
+    /// Return a parameter as an unsigned integer.
+    fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get_parsed(key)
+    }
+
+    /// Return a parameter as a signed integer.
+    fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get_parsed(key)
+    }
+}
+
+impl RouteParameterExt for RouteParameter {
+    fn get_parsed<P: FromStr>(&self, key: &str) -> Option<P> {
+        self.get(key).and_then(|value| value.parse().ok())
+    }
+}
+
 /// This struct contains the route match information.
 #[derive(Debug, Clone)]
 pub struct RouteMatch<'a, T> {
     item: &'a T,
     params: RouteParameter,
+    remainder: Option<String>,
+    wildcard_ends: Option<(String, Vec<usize>)>,
+    route_name: Option<&'a str>,
+    pattern: Option<&'a str>,
+    meta: Option<&'a RouteMeta>,
+    head_fallback: bool,
 }
 
 impl<'a, T> RouteMatch<'a, T> {
     /// Create a new RouteMatch struct.
     pub fn create(item: &'a T, params: RouteParameter) -> Self {
-        RouteMatch { item, params }
+        RouteMatch {
+            item,
+            params,
+            remainder: Option::None,
+            wildcard_ends: Option::None,
+            route_name: Option::None,
+            pattern: Option::None,
+            meta: Option::None,
+            head_fallback: false,
+        }
+    }
+
+    pub(crate) fn with_remainder(mut self, remainder: Option<String>) -> Self {
+        self.remainder = remainder;
+        self
+    }
+
+    pub(crate) fn with_wildcard_ends(mut self, name: &str, ends: Vec<usize>) -> Self {
+        self.wildcard_ends = Option::Some((String::from(name), ends));
+        self
+    }
+
+    pub(crate) fn insert_param(&mut self, name: String, value: String) {
+        self.params.insert(name, value);
+    }
+
+    pub(crate) fn with_item<U>(self, item: &'a U) -> RouteMatch<'a, U> {
+        RouteMatch {
+            item,
+            params: self.params,
+            remainder: self.remainder,
+            wildcard_ends: self.wildcard_ends,
+            route_name: self.route_name,
+            pattern: self.pattern,
+            meta: self.meta,
+            head_fallback: self.head_fallback,
+        }
+    }
+
+    pub(crate) fn with_head_fallback(mut self, head_fallback: bool) -> Self {
+        self.head_fallback = head_fallback;
+        self
+    }
+
+    pub(crate) fn with_route_name(mut self, route_name: &'a str) -> Self {
+        self.route_name = Option::Some(route_name);
+        self
+    }
+
+    pub(crate) fn with_pattern(mut self, pattern: &'a str) -> Self {
+        self.pattern = Option::Some(pattern);
+        self
+    }
+
+    pub(crate) fn with_meta(mut self, meta: &'a RouteMeta) -> Self {
+        self.meta = Option::Some(meta);
+        self
     }
 
     /// Return the item.
     pub fn get_item(&self) -> &T {
-        &self.item
+        self.item
+    }
+
+    /// Return true if a `HEAD` request was resolved to a `GET` route.
+    ///
+    /// The response of such a match should not have a body.
+    pub fn is_head_fallback(&self) -> bool {
+        self.head_fallback
+    }
+
+    /// Return the name of the matched route, if the match came from a named route.
+    pub fn get_route_name(&self) -> Option<&str> {
+        self.route_name
+    }
+
+    /// Return the path pattern of the matched route, e.g. `/users/:id`, if the match came from a
+    /// named route.
+    ///
+    /// Unlike the requested path, it is suitable as a low-cardinality metrics or logging label.
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern
+    }
+
+    /// Return the metadata of the matched route, if the match came from a named route.
+    pub fn get_meta(&self) -> Option<&RouteMeta> {
+        self.meta
     }
 
     /// Return a reference to the parameters.
@@ -26,8 +151,421 @@ impl<'a, T> RouteMatch<'a, T> {
         &self.params
     }
 
+    /// Return a parameter.
+    pub fn param_str(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Return a parameter parsed into the given type.
+    pub fn param_as<P: FromStr>(&self, name: &str) -> Result<P, ParamError> {
+        let value = self.param_str(name).ok_or_else(|| ParamError::Missing {
+            name: String::from(name),
+        })?;
+
+        value.parse().map_err(|_| ParamError::Unparseable {
+            name: String::from(name),
+            value: String::from(value),
+        })
+    }
+
+    /// Return the part of the path captured by an unnamed wildcard, like in `/static/*`.
+    ///
+    /// Named wildcards are available as parameters under their names.
+    pub fn wildcard_remainder(&self) -> Option<&str> {
+        self.param_str(WILDCARD_PARAMETER)
+    }
+
+    /// Return the segments captured by a wildcard, e.g. `["foo", "bar", "baz"]` for
+    /// `foo/bar/baz`.
+    ///
+    /// The segments are kept as the path was split during the lookup, so a decoded `%2F` stays
+    /// inside its segment. An empty capture has no segments. `None` is returned when the
+    /// wildcard did not match, use [`WILDCARD_PARAMETER`](constant.WILDCARD_PARAMETER.html) as
+    /// the name of an unnamed wildcard.
+    pub fn wildcard_segments(&self, name: &str) -> Option<Vec<&str>> {
+        split_wildcard(&self.params, self.wildcard_ends.as_ref(), name)
+    }
+
+    /// Return the unconsumed part of the path when a greedy route matched a longer path.
+    pub fn remainder(&self) -> Option<&str> {
+        self.remainder.as_deref()
+    }
+
+    /// Insert the routing information into the extensions of a request, for the layers
+    /// handling it later.
+    ///
+    /// A clone of the parameters is inserted as a [`RouteParameter`](type.RouteParameter.html),
+    /// and the name of the route, if the match came from a named route, as a
+    /// [`MatchedRouteName`](struct.MatchedRouteName.html). They replace the values of the same
+    /// types already in the extensions, and a name left by an earlier match is removed when the
+    /// match has no route name.
+    pub fn insert_into_extensions(&self, ext: &mut Extensions) {
+        ext.insert(self.params.clone());
+        match self.route_name {
+            Option::Some(route_name) => {
+                ext.insert(MatchedRouteName(String::from(route_name)));
+            }
+            Option::None => {
+                ext.remove::<MatchedRouteName>();
+            }
+        }
+    }
+
     /// Move the parameters.
     pub fn move_params(self) -> RouteParameter {
         self.params
     }
+
+    /// Move the item reference and the parameters out of the match.
+    pub fn into_parts(self) -> (&'a T, RouteParameter) {
+        (self.item, self.params)
+    }
+
+    /// Convert the match into an [`OwnedRouteMatch`](struct.OwnedRouteMatch.html), cloning the
+    /// item.
+    pub fn into_owned(self) -> OwnedRouteMatch<T>
+    where
+        T: Clone,
+    {
+        OwnedRouteMatch {
+            item: self.item.clone(),
+            params: self.params,
+            remainder: self.remainder,
+            wildcard_ends: self.wildcard_ends,
+            route_name: self.route_name.map(String::from),
+            pattern: self.pattern.map(String::from),
+            meta: self.meta.cloned(),
+            head_fallback: self.head_fallback,
+        }
+    }
+}
+
+/// Splits the value of a wildcard at the recorded ends of its segments.
+fn split_wildcard<'p>(
+    params: &'p RouteParameter,
+    wildcard_ends: Option<&(String, Vec<usize>)>,
+    name: &str,
+) -> Option<Vec<&'p str>> {
+    let (wildcard, ends) = wildcard_ends?;
+    if wildcard != name {
+        return Option::None;
+    }
+    let value = params.get(name)?;
+    let mut start = 0;
+
+    Option::Some(
+        ends.iter()
+            .map(|&end| {
+                let segment = &value[start..end];
+                start = end + 1;
+                segment
+            })
+            .collect(),
+    )
+}
+
+/// Route match information that does not borrow the router.
+///
+/// It holds a clone of the item and owns the parameters, so it can be kept across an `.await`
+/// point. It is `'static` and `Send` when the item is. Creating it costs a clone of the item and
+/// the metadata, and the allocation of the route name and the pattern, so prefer
+/// [`RouteMatch`](struct.RouteMatch.html) when the router outlives the request anyway.
+#[derive(Debug, Clone)]
+pub struct OwnedRouteMatch<T> {
+    item: T,
+    params: RouteParameter,
+    remainder: Option<String>,
+    wildcard_ends: Option<(String, Vec<usize>)>,
+    route_name: Option<String>,
+    pattern: Option<String>,
+    meta: Option<RouteMeta>,
+    head_fallback: bool,
+}
+
+impl<T> OwnedRouteMatch<T> {
+    /// Return the item.
+    pub fn get_item(&self) -> &T {
+        &self.item
+    }
+
+    /// Return true if a `HEAD` request was resolved to a `GET` route.
+    pub fn is_head_fallback(&self) -> bool {
+        self.head_fallback
+    }
+
+    /// Return the name of the matched route, if the match came from a named route.
+    pub fn get_route_name(&self) -> Option<&str> {
+        self.route_name.as_deref()
+    }
+
+    /// Return the path pattern of the matched route, if the match came from a named route.
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Return the metadata of the matched route, if the match came from a named route.
+    pub fn get_meta(&self) -> Option<&RouteMeta> {
+        self.meta.as_ref()
+    }
+
+    /// Return a reference to the parameters.
+    pub fn get_params(&self) -> &RouteParameter {
+        &self.params
+    }
+
+    /// Return a parameter.
+    pub fn param_str(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Return the segments captured by a wildcard, like
+    /// [`RouteMatch::wildcard_segments`](struct.RouteMatch.html#method.wildcard_segments).
+    pub fn wildcard_segments(&self, name: &str) -> Option<Vec<&str>> {
+        split_wildcard(&self.params, self.wildcard_ends.as_ref(), name)
+    }
+
+    /// Return the unconsumed part of the path when a greedy route matched a longer path.
+    pub fn remainder(&self) -> Option<&str> {
+        self.remainder.as_deref()
+    }
+
+    /// Move the item and the parameters out of the match.
+    pub fn into_parts(self) -> (T, RouteParameter) {
+        (self.item, self.params)
+    }
+}
+
+impl<'a, T: Clone> From<RouteMatch<'a, T>> for OwnedRouteMatch<T> {
+    fn from(route_match: RouteMatch<'a, T>) -> Self {
+        route_match.into_owned()
+    }
+}
+
+/// Name of the matched route in the extensions of a request, see
+/// [`RouteMatch::insert_into_extensions`](struct.RouteMatch.html#method.insert_into_extensions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRouteName(String);
+
+impl MatchedRouteName {
+    /// Return the name of the route.
+    pub fn get_name(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Route parameter errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum ParamError {
+    /// the parameter is missing
+    #[error("parameter not found: {name}")]
+    Missing {
+        /// missing parameter
+        name: String,
+    },
+    /// the parameter cannot be parsed into the requested type
+    #[error("failed to parse parameter {name}: {value:?}")]
+    Unparseable {
+        /// parameter name
+        name: String,
+        /// original value
+        value: String,
+    },
+}
+
+/// Routing metadata of a [`RouteMatch`](struct.RouteMatch.html) without the item.
+///
+/// It can be serialized to pass the routing decision to other services.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteMatchSummary {
+    route_name: Option<String>,
+    params: RouteParameter,
+}
+
+#[cfg(feature = "serde")]
+impl RouteMatchSummary {
+    /// Return the name of the matched route.
+    pub fn get_route_name(&self) -> Option<&str> {
+        self.route_name.as_deref()
+    }
+
+    /// Return a reference to the parameters.
+    pub fn get_params(&self) -> &RouteParameter {
+        &self.params
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, T> From<&RouteMatch<'a, T>> for RouteMatchSummary {
+    fn from(route_match: &RouteMatch<'a, T>) -> Self {
+        RouteMatchSummary {
+            route_name: route_match.route_name.map(String::from),
+            params: route_match.params.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{MatchedRouteName, OwnedRouteMatch, ParamError};
+    use crate::{RouteMatch, RouteMeta, RouteParameter, RouteParameterExt};
+    use http::Extensions;
+    use rstest::*;
+
+    #[rstest]
+    fn test_param_as() {
+        let item = 0;
+        let mut params = RouteParameter::new();
+        params.insert(String::from("id"), String::from("42"));
+        params.insert(String::from("name"), String::from("foo"));
+
+        let route_match = RouteMatch::create(&item, params);
+
+        assert_eq!(route_match.param_str("name"), Option::Some("foo"));
+        assert_eq!(route_match.param_str("missing"), Option::None);
+        assert_eq!(route_match.param_as::<u64>("id").unwrap(), 42);
+        assert_eq!(
+            route_match.param_as::<u64>("name").unwrap_err(),
+            ParamError::Unparseable {
+                name: String::from("name"),
+                value: String::from("foo"),
+            }
+        );
+        assert_eq!(
+            route_match.param_as::<u64>("missing").unwrap_err(),
+            ParamError::Missing {
+                name: String::from("missing"),
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_into_parts() {
+        let item = 7;
+        let mut params = RouteParameter::new();
+        params.insert(String::from("id"), String::from("42"));
+
+        let (matched, params) = RouteMatch::create(&item, params).into_parts();
+
+        assert_eq!(matched, &7);
+        assert_eq!(params.get("id").unwrap(), "42");
+    }
+
+    #[rstest]
+    fn test_into_owned() {
+        fn assert_static_send<S: Send + 'static>(_: &S) {}
+
+        let owned: OwnedRouteMatch<String> = {
+            let item = String::from("users");
+            let mut params = RouteParameter::new();
+            params.insert(String::from("id"), String::from("42"));
+            params.insert(String::from("rest"), String::from("a/b"));
+            let mut meta = RouteMeta::new();
+            meta.insert(String::from("auth"), String::from("admin"));
+            RouteMatch::create(&item, params)
+                .with_route_name("users.show")
+                .with_pattern("/users/:id")
+                .with_meta(&meta)
+                .with_wildcard_ends("rest", vec![1, 3])
+                .into_owned()
+        };
+
+        assert_static_send(&owned);
+        assert_eq!(owned.get_item(), "users");
+        assert_eq!(owned.param_str("id"), Option::Some("42"));
+        assert_eq!(owned.get_route_name(), Option::Some("users.show"));
+        assert_eq!(owned.pattern(), Option::Some("/users/:id"));
+        assert_eq!(owned.remainder(), Option::None);
+        assert_eq!(
+            owned.get_meta().unwrap().get("auth").map(String::as_str),
+            Option::Some("admin")
+        );
+        assert_eq!(
+            owned.wildcard_segments("rest"),
+            Option::Some(vec!["a", "b"])
+        );
+        assert_eq!(owned.wildcard_segments("id"), Option::None);
+        assert!(!owned.is_head_fallback());
+
+        let (item, params) = owned.into_parts();
+        assert_eq!(item, "users");
+        assert_eq!(params.get("id").unwrap(), "42");
+    }
+
+    #[rstest]
+    fn test_insert_into_extensions() {
+        let item = 0;
+        let mut params = RouteParameter::new();
+        params.insert(String::from("id"), String::from("42"));
+        let route_match = RouteMatch::create(&item, params.clone());
+
+        let mut ext = Extensions::new();
+        route_match.insert_into_extensions(&mut ext);
+        assert_eq!(ext.get::<RouteParameter>(), Option::Some(&params));
+        assert!(ext.get::<MatchedRouteName>().is_none());
+
+        route_match
+            .clone()
+            .with_route_name("users.show")
+            .insert_into_extensions(&mut ext);
+        assert_eq!(
+            ext.get::<MatchedRouteName>().unwrap().get_name(),
+            "users.show"
+        );
+
+        route_match.insert_into_extensions(&mut ext);
+        assert!(ext.get::<MatchedRouteName>().is_none());
+    }
+
+    #[rstest]
+    fn test_route_parameter_ext() {
+        let mut params = RouteParameter::new();
+        params.insert(String::from("id"), String::from("42"));
+        params.insert(String::from("offset"), String::from("-7"));
+        params.insert(String::from("name"), String::from("foo"));
+
+        assert_eq!(params.get_u64("id"), Option::Some(42));
+        assert_eq!(params.get_i64("id"), Option::Some(42));
+        assert_eq!(params.get_u64("offset"), Option::None);
+        assert_eq!(params.get_i64("offset"), Option::Some(-7));
+        assert_eq!(params.get_u64("name"), Option::None);
+        assert_eq!(params.get_u64("missing"), Option::None);
+        assert_eq!(params.get_parsed::<u8>("id"), Option::Some(42));
+        assert_eq!(params.get_parsed::<char>("name"), Option::None);
+    }
+
+    #[rstest]
+    fn test_route_parameter_iteration() {
+        let mut params = RouteParameter::new();
+        assert!(params.is_empty());
+
+        params.insert(String::from("id"), String::from("42"));
+        params.insert(String::from("name"), String::from("foo"));
+        assert_eq!(params.len(), 2);
+
+        for (_, value) in &mut params {
+            value.push('!');
+        }
+
+        let mut borrowed: Vec<(&String, &String)> = (&params).into_iter().collect();
+        borrowed.sort();
+        assert_eq!(
+            borrowed,
+            vec![
+                (&String::from("id"), &String::from("42!")),
+                (&String::from("name"), &String::from("foo!"))
+            ]
+        );
+
+        let mut owned: Vec<(String, String)> = params.into_iter().collect();
+        owned.sort();
+        assert_eq!(
+            owned,
+            vec![
+                (String::from("id"), String::from("42!")),
+                (String::from("name"), String::from("foo!"))
+            ]
+        );
+    }
 }
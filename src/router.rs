@@ -1,10 +1,30 @@
+use crate::locale::{LocaleSegment, LOCALE_PARAMETER};
+use crate::path::{normalize_method, Item, STANDARD_METHODS};
+use crate::ConflictPolicy;
+use crate::LookupOptions;
+use crate::OwnedRouteMatch;
+use crate::Path;
 use crate::PathError;
+use crate::PredicateContext;
+use crate::RouteIndex;
 use crate::RouteMatch;
+use crate::RouterStats;
+use crate::TraceStep;
 use crate::TreeError;
-use crate::{map::Map, RouteParameter};
-use crate::{route::Route, tree::Tree};
+use crate::ANY_METHOD;
+use crate::{map::Map, map::RouteMap, map::SmallMap, RouteParameter};
+use crate::{route::Route, tree::Tree, tree::PATH_SEPARATOR};
 use http::Method;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{
+    de::Error as _, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::sync::Arc;
 use thiserror::Error;
 use url::ParseError;
 use url::Url;
@@ -15,7 +35,11 @@ pub trait RouteResolver {
     type Item;
 
     /// Resolve a route.
-    fn resolve(&self, method: &Method, path: &str) -> Result<RouteMatch<Self::Item>, RouterError>;
+    fn resolve(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<RouteMatch<'_, Self::Item>, RouterError>;
 }
 
 /// Link to a route.
@@ -25,188 +49,4219 @@ pub trait Linker {
 }
 
 /// The main router structure.
+///
+/// The route paths are stored in a [`RouteIndex`](trait.RouteIndex.html), which is a tree by
+/// default.
 #[derive(Debug, Clone)]
-pub struct Router<T: Clone + Debug> {
-    routes: Map<String, Route<T>>,
-    tree: Tree<String>,
+pub struct Router<T: Debug, I: RouteIndex<String> = Tree<String>> {
+    routes: RouteMap<Route<T>>,
+    tree: I,
     base: Url,
+    locale: Option<LocaleSegment>,
+    fast_path: Option<FastPath<T>>,
+    static_routes: Option<Map<String, SmallMap<Method, String>>>,
+    predicate_groups: Map<String, Vec<String>>,
+    path_anchors: Map<String, String>,
+    fallback: Option<T>,
+    lookup_options: LookupOptions,
+    trailing_slash: TrailingSlash,
+    dot_segments: DotSegments,
+    head_fallback: bool,
+    reject_encoded_separators: bool,
+    max_path_depth: Option<usize>,
+    conflict_policy: ConflictPolicy,
+}
+
+/// Trailing slash handling of the router.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrailingSlash {
+    /// Paths are resolved as they are.
+    #[default]
+    Strict,
+    /// When a path is not found, it is resolved again with the trailing slash added or removed.
+    Relaxed,
+}
+
+/// Handling of the `.` and `..` segments of the resolved paths.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DotSegments {
+    /// The segments are matched literally, like any other segment.
+    #[default]
+    Literal,
+    /// The segments are removed like in a file system path before the lookup: `.` is dropped
+    /// and `..` drops the segment before it, but never goes above the root. Their percent
+    /// encoded forms, like `%2e%2e`, are removed as well.
+    Normalize,
+    /// Paths with such segments, or their percent encoded forms, are rejected with
+    /// [`RouterError::DotSegment`](enum.RouterError.html).
+    Reject,
+}
+
+/// Returns 1 for `.`, 2 for `..` and 0 for other segments, with the dots percent encoded or
+/// not.
+fn dot_segment(segment: &str) -> usize {
+    let mut rest = segment;
+    let mut dots = 0;
+    while let Option::Some(next) = rest
+        .strip_prefix('.')
+        .or_else(|| strip_prefix_ignore_case(rest, "%2e"))
+    {
+        rest = next;
+        dots += 1;
+    }
+
+    if rest.is_empty() && dots <= 2 {
+        dots
+    } else {
+        0
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    value
+        .get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &value[prefix.len()..])
+}
+
+/// Removes the dot segments of a path, keeping a trailing slash after them, e.g. `/a/b/..`
+/// becomes `/a/`.
+fn normalize_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing = false;
+    for segment in path
+        .split(PATH_SEPARATOR)
+        .filter(|segment| !segment.is_empty())
+    {
+        let dots = dot_segment(segment);
+        match dots {
+            0 => segments.push(segment),
+            1 => {}
+            _ => {
+                segments.pop();
+            }
+        }
+        trailing = dots > 0;
+    }
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push_str(PATH_SEPARATOR);
+        normalized.push_str(segment);
+    }
+    if normalized.is_empty() || trailing || path.ends_with(PATH_SEPARATOR) {
+        normalized.push_str(PATH_SEPARATOR);
+    }
+
+    normalized
+}
+
+#[derive(Debug, Clone)]
+struct FastPath<T> {
+    method: Method,
+    path: String,
+    item: T,
 }
 
 impl<T> Router<T>
 where
-    T: Clone + Debug,
+    T: Debug,
 {
     /// Create a new router with a given base url.
     ///
     /// The base url is used to generate links.
     pub fn new(base: Url) -> Self {
+        Router::with_index(base, Tree::new())
+    }
+
+    /// Create a builder for a router with a given base url.
+    ///
+    /// The builder collects the settings of the router before creating it.
+    pub fn builder(base: Url) -> RouterBuilder<T> {
+        RouterBuilder {
+            router: Router::new(base),
+        }
+    }
+
+    /// Create a new router with the given routes.
+    ///
+    /// Unlike adding the routes one by one, every route is tried, and all failures are returned
+    /// together, e.g. to report every invalid route of a configuration at once.
+    pub fn try_from_routes(
+        base: Url,
+        routes: impl IntoIterator<Item = Route<T>>,
+    ) -> Result<Self, Vec<RouterError>> {
+        let mut router = Router::new(base);
+        let errors: Vec<RouterError> = routes
+            .into_iter()
+            .filter_map(|route| router.add(route).err())
+            .collect();
+
+        if errors.is_empty() {
+            Result::Ok(router)
+        } else {
+            Result::Err(errors)
+        }
+    }
+
+    /// Create a new router with the given routes, like
+    /// [`try_from_routes`](#method.try_from_routes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the routes cannot be added. This is meant for tests and statically
+    /// known routes.
+    pub fn from_routes(base: Url, routes: impl IntoIterator<Item = Route<T>>) -> Self {
+        match Router::try_from_routes(base, routes) {
+            Result::Ok(router) => router,
+            Result::Err(errors) => panic!("failed to add routes: {:?}", errors),
+        }
+    }
+}
+
+impl<T, I> Router<T, I>
+where
+    T: Debug,
+    I: RouteIndex<String>,
+{
+    /// Create a new router with a given base url and route index.
+    ///
+    /// The index must be empty.
+    pub fn with_index(base: Url, index: I) -> Self {
         Router {
-            routes: Map::new(),
-            tree: Tree::new(),
+            routes: RouteMap::new(),
+            tree: index,
             base,
+            locale: Option::None,
+            fast_path: Option::None,
+            static_routes: Option::None,
+            predicate_groups: Map::new(),
+            path_anchors: Map::new(),
+            fallback: Option::None,
+            lookup_options: LookupOptions::default(),
+            trailing_slash: TrailingSlash::default(),
+            dot_segments: DotSegments::default(),
+            head_fallback: false,
+            reject_encoded_separators: false,
+            max_path_depth: Option::None,
+            conflict_policy: ConflictPolicy::default(),
+        }
+    }
+
+    /// Return the routes whose paths start with the static segments of the prefix, sorted by
+    /// their name, e.g. every route under `/api`.
+    ///
+    /// The segments of the prefix are matched exactly, it cannot contain parameters. When no
+    /// route is registered under the prefix, the result is empty. The routes are listed by the
+    /// [`RouteIndex::items_under`](trait.RouteIndex.html#method.items_under) of the index.
+    pub fn routes_under(&self, prefix: &str) -> Vec<&Route<T>> {
+        let mut routes: Vec<&Route<T>> = self
+            .tree
+            .items_under(prefix)
+            .into_iter()
+            .flat_map(|name| self.group_routes(name))
+            .collect();
+        routes.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        // A route is listed once even when several of its aliases are under the prefix.
+        routes.dedup_by(|a, b| a.get_name() == b.get_name());
+
+        routes
+    }
+
+    /// Visits every path of the route index depth first, with its pattern, e.g. `/users/:id`,
+    /// and its methods sorted by their name.
+    ///
+    /// Static segments are visited in the order of their names, before the parameters and
+    /// wildcards. The fast path and the fallback are not part of the tree, so they are not
+    /// visited.
+    pub fn walk<F>(&self, f: F)
+    where
+        F: FnMut(&str, &[Method]),
+    {
+        self.tree.walk(f);
+    }
+
+    /// Return the names of every route whose pattern matches the concrete path, for any method,
+    /// in the order of their precedence, e.g. both `users.me` for `/users/me` and `users.show`
+    /// for `/users/:id` on `/users/me`.
+    ///
+    /// The routes of the same path are ordered by their method names. The path is matched as it
+    /// is: the locale segment, the trailing slash and the lookup settings are not applied. The
+    /// routes are listed by the
+    /// [`RouteIndex::matching_items`](trait.RouteIndex.html#method.matching_items) of the index.
+    pub fn shadows(&self, path: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for route in self
+            .tree
+            .matching_items(path)
+            .into_iter()
+            .flat_map(|name| self.group_routes(name))
+        {
+            // A route matching through several of its aliases is listed at its first match.
+            if !names.contains(&route.get_name()) {
+                names.push(route.get_name());
+            }
+        }
+
+        names
+    }
+
+    /// Remove every route, keeping the base url and the settings of the router.
+    ///
+    /// The fast path and the fallback are settings as well, so they are kept.
+    pub fn clear(&mut self) -> &mut Self {
+        self.routes = RouteMap::new();
+        self.tree.clear();
+        self.predicate_groups = Map::new();
+        self.path_anchors = Map::new();
+        self.static_routes = Option::None;
+
+        self
+    }
+
+    /// Transform the items of the router, e.g. from handler ids to the handlers once they are
+    /// built.
+    ///
+    /// The index stores route names, so it is kept as it is instead of adding every route
+    /// again. The items of the routes are transformed first, then the fast path item and the
+    /// fallback item.
+    pub fn map<U, F>(self, mut f: F) -> Router<U, I>
+    where
+        U: Debug,
+        F: FnMut(T) -> U,
+    {
+        let mut routes = RouteMap::new();
+        routes.reserve(self.routes.len());
+        for (name, route) in self.routes {
+            routes.insert(name, route.map_item(&mut f));
+        }
+        let fast_path = self.fast_path.map(|fast_path| FastPath {
+            method: fast_path.method,
+            path: fast_path.path,
+            item: f(fast_path.item),
+        });
+
+        Router {
+            routes,
+            tree: self.tree,
+            base: self.base,
+            locale: self.locale,
+            fast_path,
+            static_routes: self.static_routes,
+            predicate_groups: self.predicate_groups,
+            path_anchors: self.path_anchors,
+            fallback: self.fallback.map(f),
+            lookup_options: self.lookup_options,
+            trailing_slash: self.trailing_slash,
+            dot_segments: self.dot_segments,
+            head_fallback: self.head_fallback,
+            reject_encoded_separators: self.reject_encoded_separators,
+            max_path_depth: self.max_path_depth,
+            conflict_policy: self.conflict_policy,
         }
     }
 
     /// Add a route to the router.
+    ///
+    /// A route whose path conflicts with a registered one is handled by the
+    /// [conflict policy](#method.set_conflict_policy). The routes that are not registered
+    /// anymore are removed from the router.
+    ///
+    /// The [aliases](struct.Route.html#method.with_alias) of the route are registered with its
+    /// path. When one of them is rejected or ignored, none of them are kept.
     pub fn add(&mut self, r: Route<T>) -> Result<&mut Self, RouterError> {
+        self.add_displacing(r, &mut Vec::new())
+    }
+
+    /// Adds a route like [`add`](#method.add), collecting the routes it removes from the router
+    /// into `replaced`, so they can be added again.
+    fn add_displacing(
+        &mut self,
+        r: Route<T>,
+        replaced: &mut Vec<Route<T>>,
+    ) -> Result<&mut Self, RouterError> {
         let name = String::from(r.get_name());
 
         if self.routes.contains_key(&name) {
             return Result::Err(RouterError::RouteAlreadyExists { route_name: name });
         }
+        if !r.get_aliases().is_empty() && r.has_predicate() {
+            return Result::Err(RouterError::AliasWithPredicate { route_name: name });
+        }
+        if r.get_aliases().is_empty() && (r.has_predicate() || !self.predicate_groups.is_empty()) {
+            if let Option::Some(anchor) = self.find_group_anchor(&r) {
+                return self.add_to_group(anchor, r);
+            }
+        }
 
-        self.tree
-            .add(r.get_path().clone(), name.clone())
-            .map_err(|te| RouterError::TreeError { tree_error: te })?;
+        // The result is whether the route is kept, the paths already registered are removed
+        // again when one of them is rejected or ignored.
+        let mut displaced = Vec::new();
+        let mut added = Vec::new();
+        let mut result = Result::Ok(true);
+        for path in r.paths() {
+            result = match self
+                .tree
+                .add_with(path.clone(), name.clone(), self.conflict_policy)
+            {
+                Result::Ok(names) if !names.contains(&name) => {
+                    displaced.extend(names);
+                    added.push(path);
+                    continue;
+                }
+                // With the replace policy, the route only displaces itself when two of its
+                // paths conflict.
+                Result::Ok(names) if self.conflict_policy == ConflictPolicy::ReplaceExisting => {
+                    displaced.extend(names.into_iter().filter(|other| *other != name));
+                    added.push(path);
+                    Result::Err(TreeError::PathAlreadyRegistered {
+                        route: path.render_original(),
+                    })
+                }
+                Result::Ok(_) => Result::Ok(false),
+                Result::Err(error) => Result::Err(error),
+            };
+            break;
+        }
+        if !matches!(result, Result::Ok(true)) {
+            for path in added.drain(..) {
+                let _ = self.tree.remove(path);
+            }
+            // The routes replaced by the paths added before the failure are registered again.
+            for displaced_name in displaced.drain(..) {
+                if let Option::Some(displaced_route) = self.routes.get(&displaced_name) {
+                    for path in displaced_route.paths() {
+                        let _ = self.tree.add_with(
+                            path.clone(),
+                            displaced_name.clone(),
+                            ConflictPolicy::KeepExisting,
+                        );
+                    }
+                }
+            }
+        }
+        for displaced_name in displaced {
+            let members: Vec<Route<T>> = self
+                .predicate_groups
+                .remove(&displaced_name)
+                .unwrap_or_default()
+                .iter()
+                .filter(|member| **member != displaced_name)
+                .filter_map(|member| self.routes.remove(member))
+                .collect();
+            if let Option::Some(displaced_route) = self.routes.remove(&displaced_name) {
+                let displaced_path = displaced_route.get_path().to_string();
+                if self.path_anchors.get(&displaced_path) == Option::Some(&displaced_name) {
+                    self.path_anchors.remove(&displaced_path);
+                }
+                // The other paths of the displaced route still point to it in the index.
+                for path in displaced_route.paths() {
+                    if added
+                        .iter()
+                        .all(|other| other.to_string() != path.to_string())
+                    {
+                        let _ = self.tree.remove(path);
+                    }
+                }
+                replaced.push(displaced_route);
+            }
+            replaced.extend(members);
+        }
+        if !result? {
+            return Result::Ok(self);
+        }
+        if r.has_predicate() {
+            self.predicate_groups
+                .insert(name.clone(), vec![name.clone()]);
+        }
+        if r.get_aliases().is_empty() {
+            self.path_anchors
+                .insert(r.get_path().to_string(), name.clone());
+        }
         self.routes.insert(name, r);
+        self.static_routes = Option::None;
 
         Result::Ok(self)
     }
 
-    /// Resolve a route.
-    pub fn resolve(&self, method: &Method, path: &str) -> Result<RouteMatch<T>, RouterError> {
-        self.tree
-            .lookup(method, path)
-            .and_then(|route_match| {
-                self.routes
-                    .get(route_match.get_item())
-                    .map(|route| RouteMatch::create(route.get_item(), route_match.move_params()))
-                    .ok_or_else(|| TreeError::PathNotFound {
-                        path: String::from(path),
-                    })
-            })
-            .map_err(|te| RouterError::TreeError { tree_error: te })
+    /// Returns the name the group of routes sharing the method and the path of the route is
+    /// registered under in the index.
+    ///
+    /// A route without a predicate only shares a group that already exists, a route with a
+    /// predicate also starts one with a route of the same path.
+    fn find_group_anchor(&self, r: &Route<T>) -> Option<String> {
+        self.path_anchors
+            .get(&r.get_path().to_string())
+            .filter(|anchor| r.has_predicate() || self.predicate_groups.contains_key(*anchor))
+            .cloned()
     }
 
-    /// Create a link to a given route and parameters.
-    pub fn link(
-        &self,
-        route_name: &str,
-        route_params: Map<String, String>,
-    ) -> Result<Url, RouterError> {
-        self.routes
-            .get(route_name)
+    /// Adds a route to a group, the routes with a predicate before the one without.
+    fn add_to_group(&mut self, anchor: String, r: Route<T>) -> Result<&mut Self, RouterError> {
+        let name = String::from(r.get_name());
+        let routes = &self.routes;
+        let group = self
+            .predicate_groups
+            .entry(anchor.clone())
+            .or_insert_with(|| vec![anchor]);
+        let unconditional = group.iter().position(|member| {
+            routes
+                .get(member)
+                .is_some_and(|route| !route.has_predicate())
+        });
+        match (r.has_predicate(), unconditional) {
+            (true, Option::Some(position)) => group.insert(position, name.clone()),
+            (true, Option::None) | (false, Option::None) => group.push(name.clone()),
+            (false, Option::Some(_)) => {
+                return Result::Err(RouterError::from(TreeError::PathAlreadyRegistered {
+                    route: r.get_path().render_original(),
+                }))
+            }
+        }
+        self.routes.insert(name, r);
+        self.static_routes = Option::None;
+
+        Result::Ok(self)
+    }
+
+    /// Add a route that matches any method.
+    ///
+    /// Routes registered for a specific method at the same path take precedence. The route is
+    /// registered with the [`ANY_METHOD`](constant.ANY_METHOD.html) method, and
+    /// [`allowed_methods`](#method.allowed_methods) lists every standard method for it.
+    pub fn add_any_method(
+        &mut self,
+        name: &str,
+        path: &str,
+        item: T,
+    ) -> Result<&mut Self, RouterError> {
+        let method =
+            Method::from_bytes(ANY_METHOD.as_bytes()).expect("the any method is a valid method");
+
+        self.add(Route::create(name, method, path, item)?)
+    }
+
+    /// Add a route from a line like `GET /users/:id`, parsed by the `TryFrom<&str>`
+    /// implementation of [`Path`](struct.Path.html).
+    ///
+    /// The route is named after its method and path as they are displayed, e.g. `GET /users/:id`.
+    pub fn add_line(&mut self, line: &str, item: T) -> Result<&mut Self, RouterError> {
+        let path = Path::try_from(line)?;
+
+        self.add(Route::from_path(&path.to_string(), path, item))
+    }
+
+    /// Remove a route from the router.
+    ///
+    /// The removed route is returned, so it can be added again later.
+    pub fn remove(&mut self, route_name: &str) -> Result<Route<T>, RouterError> {
+        let route = self
+            .routes
+            .remove(route_name)
             .ok_or_else(|| RouterError::RouteNotFound {
                 route_name: String::from(route_name),
+            })?;
+
+        // The route is kept when the index fails to remove it, so that none of its paths point
+        // to a missing route.
+        if let Result::Err(err) = self.remove_from_index(route_name, &route) {
+            self.routes.insert(String::from(route_name), route);
+            return Result::Err(err);
+        }
+        self.static_routes = Option::None;
+
+        Result::Ok(route)
+    }
+
+    /// Removes the paths of a route from the index, or hands the path of a group over to the
+    /// next route of the group. The index is left as it was on failure.
+    fn remove_from_index(&mut self, route_name: &str, route: &Route<T>) -> Result<(), RouterError> {
+        let anchor = self
+            .predicate_groups
+            .iter()
+            .find(|(_, group)| group.iter().any(|member| member == route_name))
+            .map(|(anchor, _)| anchor.clone());
+        let anchor = match anchor {
+            Option::None => {
+                for (index, path) in route.paths().enumerate() {
+                    if let Result::Err(err) = self.tree.remove(path) {
+                        for path in route.paths().take(index) {
+                            let _ = self.tree.add(path.clone(), String::from(route_name));
+                        }
+                        return Result::Err(RouterError::from(err));
+                    }
+                }
+                let path = route.get_path().to_string();
+                if self.path_anchors.get(&path).map(String::as_str) == Option::Some(route_name) {
+                    self.path_anchors.remove(&path);
+                }
+                return Result::Ok(());
+            }
+            Option::Some(anchor) => anchor,
+        };
+
+        let mut group = self
+            .predicate_groups
+            .get(&anchor)
+            .cloned()
+            .unwrap_or_default();
+        group.retain(|member| member != route_name);
+        let next_anchor = if anchor == route_name {
+            // The index held the removed route, the next route of the group takes its place.
+            self.tree.remove(route.get_path())?;
+            match group.first().and_then(|first| self.routes.get(first)) {
+                Option::Some(first) => {
+                    if let Result::Err(err) = self
+                        .tree
+                        .add(first.get_path().clone(), String::from(first.get_name()))
+                    {
+                        let _ = self
+                            .tree
+                            .add(route.get_path().clone(), String::from(route_name));
+                        return Result::Err(RouterError::from(err));
+                    }
+                    Option::Some(group[0].clone())
+                }
+                Option::None => Option::None,
+            }
+        } else {
+            Option::Some(anchor.clone())
+        };
+        let path = route.get_path().to_string();
+        match &next_anchor {
+            Option::Some(next_anchor) => self.path_anchors.insert(path, next_anchor.clone()),
+            Option::None => self.path_anchors.remove(&path),
+        };
+        self.predicate_groups.remove(&anchor);
+        if let Option::Some(next_anchor) = next_anchor {
+            if group.iter().any(|member| {
+                self.routes
+                    .get(member)
+                    .is_some_and(|route| route.has_predicate())
+            }) {
+                self.predicate_groups.insert(next_anchor, group);
+            }
+        }
+
+        Result::Ok(())
+    }
+
+    /// Add every route of another router with a prefix prepended to their paths.
+    ///
+    /// Only the routes are taken over, the base url and the settings of the other router are
+    /// ignored. When a route name or a path is already registered, none of the routes are added.
+    pub fn mount(&mut self, prefix: &str, other: Router<T, I>) -> Result<&mut Self, RouterError> {
+        self.mount_routes(prefix, Option::None, other)
+    }
+
+    /// Mount another router like [`mount`](#method.mount), with the route names prefixed by
+    /// `namespace` and a dot.
+    pub fn mount_with_namespace(
+        &mut self,
+        prefix: &str,
+        namespace: &str,
+        other: Router<T, I>,
+    ) -> Result<&mut Self, RouterError> {
+        self.mount_routes(prefix, Option::Some(namespace), other)
+    }
+
+    fn mount_routes(
+        &mut self,
+        prefix: &str,
+        namespace: Option<&str>,
+        other: Router<T, I>,
+    ) -> Result<&mut Self, RouterError> {
+        let prefix = Path::parse_prefix(prefix)?;
+        let routes = other
+            .routes
+            .into_iter()
+            .map(|(name, route)| {
+                let name = match namespace {
+                    Option::Some(namespace) => format!("{}.{}", namespace, name),
+                    Option::None => name,
+                };
+
+                route.with_prefix(name, &prefix).map_err(RouterError::from)
             })
-            .and_then(|r| {
-                r.get_path()
-                    .render(route_params)
-                    .map_err(|pe| RouterError::PathError { path_error: pe })
-            })
-            .and_then(|rendered| {
-                self.base
-                    .join(rendered.as_str())
-                    .map_err(|pe| RouterError::UrlParseError { parse_error: pe })
-            })
+            .collect::<Result<Vec<Route<T>>, RouterError>>()?;
+
+        let mut added: Vec<String> = Vec::with_capacity(routes.len());
+        let mut replaced = Vec::new();
+        for route in routes {
+            let name = String::from(route.get_name());
+            if let Result::Err(err) = self.add_displacing(route, &mut replaced) {
+                self.roll_back(added, replaced);
+
+                return Result::Err(err);
+            }
+            added.push(name);
+        }
+
+        Result::Ok(self)
     }
 
-    /// Tries to compact the memory footprint of the router.
-    pub fn optimize(mut self) -> Self {
-        self.routes.optimize();
-        self.tree.optimize();
+    /// Removes the routes added by a failed mount or scope, and adds the routes they replaced
+    /// again.
+    ///
+    /// The rollback is best effort, its errors are ignored so that the error of the failed
+    /// route is the one returned.
+    fn roll_back(&mut self, added: Vec<String>, replaced: Vec<Route<T>>) {
+        for name in added.iter().rev() {
+            if self.routes.contains_key(name) {
+                let _ = self.remove(name);
+            }
+        }
+        // A route added and then replaced by another one of the same batch is not restored.
+        for route in replaced {
+            if !added.iter().any(|name| name == route.get_name()) {
+                let _ = self.add(route);
+            }
+        }
+    }
+
+    /// Add routes sharing a path prefix.
+    ///
+    /// The routes added to the scope in the closure get the prefix prepended to their paths.
+    /// The prefix cannot contain wildcard or optional items. When adding any of the routes
+    /// fails, the routes of the scope are removed, the routes they replaced are added again and
+    /// the first error is returned.
+    pub fn scope<F>(&mut self, prefix: &str, f: F) -> Result<&mut Self, RouterError>
+    where
+        F: FnOnce(&mut Scope<'_, T, I>),
+    {
+        let prefix = Path::parse_prefix(prefix)?;
+        let mut scope = Scope {
+            router: self,
+            prefix,
+            added: Vec::new(),
+            replaced: Vec::new(),
+            error: Option::None,
+        };
+        f(&mut scope);
+
+        let (added, replaced) = (scope.added, scope.replaced);
+        match scope.error {
+            Option::None => Result::Ok(self),
+            Option::Some(err) => {
+                self.roll_back(added, replaced);
+
+                Result::Err(err)
+            }
+        }
+    }
+
+    /// Return a route by its name.
+    pub fn route(&self, name: &str) -> Option<&Route<T>> {
+        self.routes.get(name)
+    }
+
+    /// Iterate over the registered routes.
+    ///
+    /// With the `indexmap` feature the routes are iterated in the order they were added,
+    /// otherwise in no particular order.
+    pub fn routes(&self) -> impl Iterator<Item = &Route<T>> {
+        self.routes.iter().map(|(_, route)| route)
+    }
+
+    /// Return the routes registered for a method, sorted by their name, e.g. every route
+    /// accepting `DELETE`.
+    ///
+    /// The routes added with [`add_any_method`](#method.add_any_method) are only returned for
+    /// [`ANY_METHOD`](constant.ANY_METHOD.html).
+    pub fn routes_for_method(&self, method: &Method) -> Vec<&Route<T>> {
+        let normalized = normalize_method(method);
+        let method = normalized.as_ref().unwrap_or(method);
+        let mut routes: Vec<&Route<T>> = self
+            .routes()
+            .filter(|route| route.get_path().get_method() == method)
+            .collect();
+        routes.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        routes
+    }
+
+    /// Treat the first segment of the resolved paths as a locale.
+    ///
+    /// The matching locale is captured in the [`LOCALE_PARAMETER`](constant.LOCALE_PARAMETER.html)
+    /// parameter, and the rest of the path is resolved as usual. Locales are compared ignoring
+    /// case and the difference between `-` and `_`.
+    pub fn set_locale_segment(&mut self, supported: &[&str]) -> &mut Self {
+        self.locale
+            .get_or_insert_with(LocaleSegment::new)
+            .set_supported(
+                supported
+                    .iter()
+                    .map(|locale| String::from(*locale))
+                    .collect(),
+            );
 
         self
     }
-}
 
-impl<T> RouteResolver for Router<T>
-where
-    T: Clone + Debug,
-{
-    type Item = T;
+    /// Set the locale used when the first segment is not a supported locale.
+    ///
+    /// In this case the whole path is resolved and the default locale is captured.
+    pub fn set_default_locale(&mut self, locale: &str) -> &mut Self {
+        self.locale
+            .get_or_insert_with(LocaleSegment::new)
+            .set_default(String::from(locale));
 
-    fn resolve(&self, method: &Method, path: &str) -> Result<RouteMatch<Self::Item>, RouterError> {
-        self.resolve(method, path)
+        self
     }
-}
 
-impl<T> Linker for Router<T>
-where
-    T: Clone + Debug,
-{
-    fn link(&self, route_name: &str, route_params: RouteParameter) -> Result<Url, RouterError> {
-        self.link(route_name, route_params)
+    /// Return the base url used to generate links.
+    pub fn base(&self) -> &Url {
+        &self.base
     }
-}
 
-/// router errors
-#[derive(Error, Debug, PartialEq)]
-pub enum RouterError {
-    /// the route already exists
-    #[error("route already exists")]
-    RouteAlreadyExists {
-        /// already existing route
-        route_name: String,
-    },
-    /// route not found
-    #[error("route not found")]
-    RouteNotFound {
-        /// missing route
-        route_name: String,
-    },
-    /// router tree error
-    #[error("route tree error: {tree_error}")]
-    TreeError {
-        /// tree error
-        tree_error: TreeError,
-    },
-    /// router path error
-    #[error("route path error: {path_error}")]
-    PathError {
-        /// path error
-        path_error: PathError,
-    },
-    /// url parser error
-    #[error("failed to parse url: {parse_error}")]
-    UrlParseError {
-        /// url parse error
-        parse_error: ParseError,
-    },
-}
+    /// Reserve room for at least `additional` more routes, e.g. before adding a large generated
+    /// route table, to avoid growing the route map repeatedly.
+    ///
+    /// The nodes of the route index are still allocated as the routes are added. The unused
+    /// room can be released with [`optimize`](#method.optimize).
+    pub fn reserve(&mut self, additional: usize) -> &mut Self {
+        self.routes.reserve(additional);
 
-#[cfg(test)]
-mod tests {
+        self
+    }
 
-    use crate::{map::Map, Linker};
-    use crate::{Route, RouteResolver, Router};
-    use http::Method;
-    use rand::Rng;
-    use rstest::*;
-    use url::Url;
+    /// Set the base url used to generate links.
+    pub fn set_base(&mut self, base: Url) -> &mut Self {
+        self.base = base;
 
-    #[rstest]
-    fn test_happy_path() {
-        let mut rng = rand::thread_rng();
+        self
+    }
 
-        let root_item: u64 = rng.gen();
+    /// Set a route that is checked before the others.
+    ///
+    /// The method and the path are compared directly with the resolved ones, so the route
+    /// cannot have parameters. This is meant for a single, frequently requested endpoint, like
+    /// a health check.
+    pub fn set_fast_path(&mut self, method: Method, path: &str, item: T) -> &mut Self {
+        self.fast_path = Option::Some(FastPath {
+            method: normalize_method(&method).unwrap_or(method),
+            path: String::from(path),
+            item,
+        });
 
-        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        self
+    }
 
-        assert!(router
-            .add(Route::create("root", Method::GET, "/", root_item).unwrap())
-            .is_ok());
-        assert!(router
-            .add(Route::create("root", Method::GET, "/", root_item).unwrap())
-            .is_err());
+    /// Set an item that is resolved with empty parameters when no route matches the path.
+    ///
+    /// Paths that match a route, but not with the requested method, are still reported as
+    /// [`TreeError::MethodNotFound`](enum.TreeError.html). The fallback is not considered by
+    /// [`matches`](#method.matches) and [`allowed_methods`](#method.allowed_methods).
+    pub fn set_fallback(&mut self, item: T) -> &mut Self {
+        self.fallback = Option::Some(item);
 
-        let router = router;
-        let m = &Method::GET;
+        self
+    }
 
-        {
-            let resolver: Box<dyn RouteResolver<Item = u64>> = Box::new(router.clone());
-            assert_eq!(resolver.resolve(m, "/").unwrap().get_item(), &root_item);
-        }
+    /// Match the static segments of the paths ignoring their case.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) -> &mut Self {
+        self.lookup_options.set_case_insensitive(case_insensitive);
 
-        {
-            let linker: Box<dyn Linker> = Box::new(router.clone());
-            assert_eq!(
-                linker.link("root", Map::new()).unwrap().to_string(),
-                "http://example.com/"
-            );
-            assert!(linker.link("asdf", Map::new()).is_err());
-        }
+        self
+    }
+
+    /// Percent-decode the path segments before matching them and capturing the parameters.
+    pub fn decode_params(&mut self, decode_params: bool) -> &mut Self {
+        self.lookup_options.set_decode_params(decode_params);
+
+        self
+    }
+
+    /// Set how the trailing slash of the resolved paths is handled.
+    ///
+    /// The default tree index already ignores empty segments, so this mostly matters for custom
+    /// indexes that distinguish `/users` from `/users/`.
+    pub fn set_trailing_slash(&mut self, trailing_slash: TrailingSlash) -> &mut Self {
+        self.trailing_slash = trailing_slash;
+
+        self
+    }
+
+    /// Set how the `.` and `..` segments of the resolved paths are handled.
+    ///
+    /// By default they are matched literally, so `/users/../admin` does not resolve to
+    /// `/admin`. When they are normalized, the cleaned path is resolved and the locale, the
+    /// fast path and the limits apply to it. When they are rejected, resolving the path fails
+    /// with [`RouterError::DotSegment`](enum.RouterError.html), and the fallback item is not
+    /// used.
+    pub fn set_dot_segments(&mut self, dot_segments: DotSegments) -> &mut Self {
+        self.dot_segments = dot_segments;
+
+        self
+    }
+
+    /// Resolve `HEAD` requests to `GET` routes when there is no `HEAD` route for the path.
+    ///
+    /// Such matches are marked with
+    /// [`RouteMatch::is_head_fallback`](struct.RouteMatch.html#method.is_head_fallback).
+    pub fn head_fallback(&mut self, head_fallback: bool) -> &mut Self {
+        self.head_fallback = head_fallback;
+
+        self
+    }
+
+    /// Reject the paths containing an encoded path separator, `%2F`.
+    ///
+    /// By default such a segment is matched as a whole, so a parameter can capture `foo%2Fbar`,
+    /// which becomes `foo/bar` when the parameters are decoded. When rejected, resolving the
+    /// path fails with [`RouterError::EncodedSeparator`](enum.RouterError.html), and the
+    /// fallback item is not used.
+    pub fn reject_encoded_separators(&mut self, reject_encoded_separators: bool) -> &mut Self {
+        self.reject_encoded_separators = reject_encoded_separators;
+
+        self
+    }
+
+    /// Limit the number of non-empty segments of the resolved paths.
+    ///
+    /// Longer paths fail with [`RouterError::PathTooDeep`](enum.RouterError.html) before the
+    /// route index is searched, and the fallback item is not used. By default the depth is not
+    /// limited.
+    pub fn max_path_depth(&mut self, limit: usize) -> &mut Self {
+        self.max_path_depth = Option::Some(limit);
+
+        self
+    }
+
+    /// Checks the path against the limits of the router before resolving it, and removes its
+    /// dot segments when they are normalized.
+    fn check_path<'p>(&self, path: &'p str) -> Result<Cow<'p, str>, RouterError> {
+        let has_dot_segments = self.dot_segments != DotSegments::Literal
+            && path
+                .split(PATH_SEPARATOR)
+                .any(|segment| dot_segment(segment) > 0);
+        let path = match (self.dot_segments, has_dot_segments) {
+            (DotSegments::Reject, true) => {
+                return Result::Err(RouterError::DotSegment {
+                    path: String::from(path),
+                })
+            }
+            (DotSegments::Normalize, true) => Cow::Owned(normalize_dot_segments(path)),
+            _ => Cow::Borrowed(path),
+        };
+
+        if let Option::Some(limit) = self.max_path_depth {
+            let mut pieces = path.split(PATH_SEPARATOR).filter(|piece| !piece.is_empty());
+            if pieces.nth(limit).is_some() {
+                return Result::Err(RouterError::PathTooDeep {
+                    depth: limit + 1 + pieces.count(),
+                    limit,
+                });
+            }
+        }
+
+        let encoded = self.reject_encoded_separators
+            && path
+                .as_bytes()
+                .windows(3)
+                .any(|window| window.eq_ignore_ascii_case(b"%2f"));
+        if encoded {
+            return Result::Err(RouterError::EncodedSeparator {
+                path: path.into_owned(),
+            });
+        }
+
+        Result::Ok(path)
+    }
+
+    /// Set how a route conflicting with a registered one is handled.
+    ///
+    /// By default such routes are rejected. The policy also applies to the parameters and
+    /// wildcards registered at the same position, where replacing a segment drops every route
+    /// registered under it.
+    pub fn set_conflict_policy(&mut self, conflict_policy: ConflictPolicy) -> &mut Self {
+        self.conflict_policy = conflict_policy;
+
+        self
+    }
+
+    /// Resolve a route.
+    pub fn resolve(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, T>, RouterError> {
+        self.resolve_canonical(method, path, Option::None, Option::None)
+            .map(|(route_match, _)| route_match)
+    }
+
+    /// Resolve a route like [`resolve`](#method.resolve), evaluating the predicates of the
+    /// routes against the context, see
+    /// [`Route::with_predicate`](struct.Route.html#method.with_predicate).
+    pub fn resolve_with(
+        &self,
+        method: &Method,
+        path: &str,
+        context: &PredicateContext<'_>,
+    ) -> Result<RouteMatch<'_, T>, RouterError> {
+        self.resolve_canonical(method, path, Option::Some(context), Option::None)
+            .map(|(route_match, _)| route_match)
+    }
+
+    /// Resolve a route like [`resolve`](#method.resolve), recording the steps of the index walk
+    /// into `trace`, e.g. to find out why a path is not found.
+    ///
+    /// The path is resolved the same way, so the steps are recorded for the segments after the
+    /// locale segment of the normalized path, and of the path with the trailing slash toggled
+    /// or of the `GET` route when the lookup is retried. The fast path and the fallback are not
+    /// traced, the result shows whether they matched. The tree records the branches leading to
+    /// the matched route, or to the first dead end of its walk, other indexes record nothing.
+    pub fn resolve_traced(
+        &self,
+        method: &Method,
+        path: &str,
+        trace: &mut Vec<TraceStep>,
+    ) -> Result<RouteMatch<'_, T>, RouterError> {
+        trace.clear();
+        self.resolve_canonical(method, path, Option::None, Option::Some(trace))
+            .map(|(route_match, _)| route_match)
+    }
+
+    /// Resolve a route into an [`OwnedRouteMatch`](struct.OwnedRouteMatch.html) that does not
+    /// borrow the router.
+    ///
+    /// It is meant for async handlers keeping the match across an `.await` point. The item is
+    /// cloned on every call, so prefer [`resolve`](#method.resolve) when the borrow is not in
+    /// the way.
+    pub fn resolve_owned(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<OwnedRouteMatch<T>, RouterError>
+    where
+        T: Clone,
+    {
+        self.resolve(method, path).map(RouteMatch::into_owned)
+    }
+
+    /// Resolve several requests at once.
+    ///
+    /// The results are in the order of the requests.
+    pub fn resolve_batch(
+        &self,
+        reqs: &[(Method, &str)],
+    ) -> Vec<Result<RouteMatch<'_, T>, RouterError>> {
+        let mut results = Vec::with_capacity(reqs.len());
+        results.extend(reqs.iter().map(|(method, path)| self.resolve(method, path)));
+
+        results
+    }
+
+    /// Resolve several requests at once, in parallel, like
+    /// [`resolve_batch`](#method.resolve_batch).
+    ///
+    /// The results are in the order of the requests.
+    #[cfg(feature = "rayon")]
+    pub fn par_resolve_batch(
+        &self,
+        reqs: &[(Method, &str)],
+    ) -> Vec<Result<RouteMatch<'_, T>, RouterError>>
+    where
+        T: Sync,
+        I: Sync,
+    {
+        reqs.par_iter()
+            .map(|(method, path)| self.resolve(method, path))
+            .collect()
+    }
+
+    /// Resolve a route from a full url.
+    ///
+    /// The scheme, the host and the port of the url must match the base url, and its path must start with
+    /// the path of the base url, like the links generated by the router. The rest of the path is
+    /// resolved like in [`resolve`](#method.resolve).
+    pub fn resolve_full(
+        &self,
+        method: &Method,
+        url: &Url,
+    ) -> Result<RouteMatch<'_, T>, RouterError> {
+        let mismatch = || RouterError::BaseMismatch {
+            url: String::from(url.as_str()),
+        };
+        if url.scheme() != self.base.scheme()
+            || url.host() != self.base.host()
+            || url.port_or_known_default() != self.base.port_or_known_default()
+        {
+            return Result::Err(mismatch());
+        }
+
+        let base_path = self.base.path().trim_end_matches(PATH_SEPARATOR);
+        let path = url.path().strip_prefix(base_path).ok_or_else(mismatch)?;
+        if !path.is_empty() && !path.starts_with(PATH_SEPARATOR) {
+            return Result::Err(mismatch());
+        }
+
+        self.resolve(
+            method,
+            if path.is_empty() {
+                PATH_SEPARATOR
+            } else {
+                path
+            },
+        )
+    }
+
+    /// Resolve a route, returning a mutable reference to its item and the parameters.
+    ///
+    /// This allows handlers to keep state without interior mutability.
+    pub fn resolve_mut(
+        &mut self,
+        method: &Method,
+        path: &str,
+    ) -> Result<(&mut T, RouteParameter), RouterError> {
+        let normalized = normalize_method(method);
+        let method = normalized.as_ref().unwrap_or(method);
+        let path = &*self.check_path(path)?;
+        let is_fast_path = self
+            .fast_path
+            .as_ref()
+            .is_some_and(|fast_path| fast_path.path == path && fast_path.method == method);
+        if is_fast_path {
+            let fast_path = self.fast_path.as_mut().expect("the fast path was matched");
+            return Result::Ok((&mut fast_path.item, RouteParameter::new()));
+        }
+
+        let (route_name, params) = self
+            .resolve_canonical(method, path, Option::None, Option::None)
+            .map(|(route_match, _)| {
+                (
+                    route_match.get_route_name().map(String::from),
+                    route_match.move_params(),
+                )
+            })?;
+        // The item is looked up again mutably, it is always found: the route was just resolved,
+        // and only the fallback is resolved without a route name, the fast path is handled above.
+        let item = match route_name {
+            Option::Some(route_name) => self
+                .routes
+                .get_mut(&route_name)
+                .expect("a resolved route is registered")
+                .get_item_mut(),
+            Option::None => self
+                .fallback
+                .as_mut()
+                .expect("only the fallback is resolved without a route name"),
+        };
+
+        Result::Ok((item, params))
+    }
+
+    /// Resolve a route, reporting non-canonical paths.
+    ///
+    /// When case insensitive matching is enabled and the casing of the path differs from the
+    /// registered route, [`ResolveError::CaseRedirect`](enum.ResolveError.html) is returned with
+    /// the canonical path instead of the match.
+    pub fn try_resolve(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<RouteMatch<'_, T>, ResolveError> {
+        match self.resolve_canonical(method, path, Option::None, Option::None)? {
+            (_, Option::Some(to)) => Result::Err(ResolveError::CaseRedirect { to }),
+            (route_match, Option::None) => Result::Ok(route_match),
+        }
+    }
+
+    fn resolve_canonical(
+        &self,
+        method: &Method,
+        path: &str,
+        context: Option<&PredicateContext<'_>>,
+        trace: Option<&mut Vec<TraceStep>>,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), RouterError> {
+        // The methods are registered with uppercase names.
+        let normalized = normalize_method(method);
+        let method = normalized.as_ref().unwrap_or(method);
+        let path = &*self.check_path(path)?;
+        if let Option::Some(fast_path) = &self.fast_path {
+            if fast_path.path == path && fast_path.method == method {
+                return Result::Ok((
+                    RouteMatch::create(&fast_path.item, RouteParameter::new()),
+                    Option::None,
+                ));
+            }
+        }
+
+        let found = match &self.locale {
+            Option::None => self.resolve_path(method, path, context, trace),
+            Option::Some(locale_segment) => match locale_segment.split(path) {
+                Option::None => Result::Err(RouterError::TreeError {
+                    tree_error: TreeError::PathNotFound {
+                        path: String::from(path),
+                    },
+                }),
+                Option::Some((locale, rest)) => {
+                    let prefix = &path[..path.len() - rest.len()];
+
+                    self.resolve_path(method, rest, context, trace).map(
+                        |(mut route_match, canonical)| {
+                            route_match
+                                .insert_param(String::from(LOCALE_PARAMETER), String::from(locale));
+                            (
+                                route_match,
+                                canonical.map(|canonical| format!("{}{}", prefix, canonical)),
+                            )
+                        },
+                    )
+                }
+            },
+        };
+
+        match (found, &self.fallback) {
+            (
+                Result::Err(RouterError::TreeError {
+                    tree_error: TreeError::PathNotFound { .. },
+                }),
+                Option::Some(fallback),
+            ) => Result::Ok((
+                RouteMatch::create(fallback, RouteParameter::new()),
+                Option::None,
+            )),
+            (found, _) => found,
+        }
+    }
+
+    fn resolve_path(
+        &self,
+        method: &Method,
+        path: &str,
+        context: Option<&PredicateContext<'_>>,
+        mut trace: Option<&mut Vec<TraceStep>>,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), RouterError> {
+        let found = match self.lookup_path(method, path, trace.as_deref_mut()) {
+            Result::Err(err) if self.head_fallback && method == Method::HEAD => self
+                .lookup_path(&Method::GET, path, trace)
+                .map(|(route_match, canonical)| (route_match.with_head_fallback(true), canonical))
+                .map_err(|_| err),
+            found => found,
+        };
+
+        found
+            .and_then(|(route_match, canonical)| {
+                self.select_route(route_match.get_item(), context)
+                    .map(|route| {
+                        let route_match = route_match
+                            .with_item(route.get_item())
+                            .with_route_name(route.get_name())
+                            .with_pattern(route.get_pattern())
+                            .with_meta(route.get_meta());
+
+                        (route_match, canonical)
+                    })
+                    .ok_or_else(|| TreeError::PathNotFound {
+                        path: String::from(path),
+                    })
+            })
+            .map_err(RouterError::from)
+    }
+
+    /// Returns the route registered in the index under `name`, or the routes of its group.
+    fn group_routes(&self, name: &str) -> Vec<&Route<T>> {
+        match self.predicate_groups.get(name) {
+            Option::None => self.routes.get(name).into_iter().collect(),
+            Option::Some(group) => group
+                .iter()
+                .filter_map(|name| self.routes.get(name))
+                .collect(),
+        }
+    }
+
+    /// Picks the route registered in the index under `name`, or the first route of its group
+    /// accepting the context.
+    fn select_route(
+        &self,
+        name: &str,
+        context: Option<&PredicateContext<'_>>,
+    ) -> Option<&Route<T>> {
+        match self.predicate_groups.get(name) {
+            Option::None => self.routes.get(name),
+            Option::Some(group) => group
+                .iter()
+                .filter_map(|name| self.routes.get(name))
+                .find(|route| route.accepts(context)),
+        }
+    }
+
+    fn lookup_path(
+        &self,
+        method: &Method,
+        path: &str,
+        mut trace: Option<&mut Vec<TraceStep>>,
+    ) -> Result<(RouteMatch<'_, String>, Option<String>), TreeError> {
+        match self.lookup(method, path, trace.as_deref_mut()) {
+            Result::Err(err @ TreeError::PathNotFound { .. }) => {
+                match self.alternate_trailing_slash(path) {
+                    Option::Some(toggled) => self.lookup(method, &toggled, trace),
+                    Option::None => Result::Err(err),
+                }
+            }
+            found => found,
+        }
+    }
+
+    /// Returns the path with its trailing slash toggled, when the trailing slash is
+    /// [relaxed](enum.TrailingSlash.html) and the path is not the root path.
+    fn alternate_trailing_slash<'p>(&self, path: &'p str) -> Option<Cow<'p, str>> {
+        if self.trailing_slash != TrailingSlash::Relaxed || path == PATH_SEPARATOR {
+            return Option::None;
+        }
+
+        Option::Some(match path.strip_suffix(PATH_SEPARATOR) {
+            Option::Some(stripped) => Cow::Borrowed(stripped),
+            Option::None => Cow::Owned(format!("{}{}", path, PATH_SEPARATOR)),
+        })
+    }
+
+    /// Whether a route would be resolved for the method and the path.
+    ///
+    /// This is cheaper than [`resolve`](#method.resolve), since the parameters are not captured.
+    /// Like in `resolve`, a route with a predicate is not matched, since there is no request to
+    /// check it against.
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        let normalized = normalize_method(method);
+        let method = normalized.as_ref().unwrap_or(method);
+        let path = &*match self.check_path(path) {
+            Result::Ok(path) => path,
+            Result::Err(_) => return false,
+        };
+        if let Option::Some(fast_path) = &self.fast_path {
+            if fast_path.path == path && fast_path.method == method {
+                return true;
+            }
+        }
+
+        let rest = match &self.locale {
+            Option::None => path,
+            Option::Some(locale_segment) => match locale_segment.split(path) {
+                Option::Some((_, rest)) => rest,
+                Option::None => return false,
+            },
+        };
+
+        self.contains(method, rest)
+            || (self.head_fallback && method == Method::HEAD && self.contains(&Method::GET, rest))
+    }
+
+    fn contains(&self, method: &Method, path: &str) -> bool {
+        if !self.predicate_groups.is_empty() {
+            return self
+                .lookup_path(method, path, Option::None)
+                .is_ok_and(|(route_match, _)| {
+                    self.select_route(route_match.get_item(), Option::None)
+                        .is_some()
+                });
+        }
+        if self.tree.contains(method, path, &self.lookup_options) {
+            return true;
+        }
+
+        self.alternate_trailing_slash(path)
+            .is_some_and(|toggled| self.tree.contains(method, &toggled, &self.lookup_options))
+    }
+
+    /// Whether any route is registered for the path, regardless of its method.
+    ///
+    /// Together with [`matches`](#method.matches) this tells a missing path (404) apart from a
+    /// missing method (405). The path is resolved like in
+    /// [`allowed_methods`](#method.allowed_methods), the fallback item is not considered.
+    pub fn path_exists(&self, path: &str) -> bool {
+        self.allowed_methods(path).is_ok()
+    }
+
+    /// Return the methods registered for a path, e.g. to answer `OPTIONS` requests.
+    ///
+    /// The path is resolved the same way as in [`resolve`](#method.resolve), but the method is
+    /// not checked. The methods only served by routes with a predicate are left out, and a route
+    /// added with [`add_any_method`](#method.add_any_method) is listed with every standard
+    /// method.
+    pub fn allowed_methods(&self, path: &str) -> Result<Vec<Method>, RouterError> {
+        let path = &*self.check_path(path)?;
+        let not_found = || RouterError::TreeError {
+            tree_error: TreeError::PathNotFound {
+                path: String::from(path),
+            },
+        };
+        let rest = match &self.locale {
+            Option::None => path,
+            Option::Some(locale_segment) => locale_segment
+                .split(path)
+                .map(|(_, rest)| rest)
+                .ok_or_else(not_found)?,
+        };
+
+        let found = match self.tree.allowed_methods(rest, &self.lookup_options) {
+            Result::Err(err @ TreeError::PathNotFound { .. }) => {
+                match self.alternate_trailing_slash(rest) {
+                    Option::Some(toggled) => {
+                        self.tree.allowed_methods(&toggled, &self.lookup_options)
+                    }
+                    Option::None => Result::Err(err),
+                }
+            }
+            found => found,
+        };
+        let found = found.and_then(|allowed| {
+            if self.predicate_groups.is_empty() {
+                return Result::Ok(allowed);
+            }
+            let allowed: Vec<Method> = allowed
+                .into_iter()
+                .filter(|method| self.contains(method, rest))
+                .collect();
+            if allowed.is_empty() {
+                return Result::Err(TreeError::PathNotFound {
+                    path: String::from(path),
+                });
+            }
+
+            Result::Ok(allowed)
+        });
+
+        let mut allowed = match (found, &self.fast_path) {
+            (found, Option::Some(fast_path)) if fast_path.path == path => {
+                let mut allowed = found.unwrap_or_default();
+                allowed.push(fast_path.method.clone());
+                allowed
+            }
+            (found, _) => found?,
+        };
+        if let Option::Some(position) = allowed
+            .iter()
+            .position(|method| method.as_str() == ANY_METHOD)
+        {
+            allowed.remove(position);
+            allowed.extend(STANDARD_METHODS.iter().cloned());
+        }
+        if self.head_fallback && allowed.contains(&Method::GET) {
+            allowed.push(Method::HEAD);
+        }
+        allowed.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        allowed.dedup();
+
+        Result::Ok(allowed)
+    }
+
+    /// Looks up a path in the index, recording the steps of the walk into `trace` when given,
+    /// in place of the ones of a previous lookup.
+    fn lookup(
+        &self,
+        method: &Method,
+        path: &str,
+        trace: Option<&mut Vec<TraceStep>>,
+    ) -> Result<(RouteMatch<'_, String>, Option<String>), TreeError> {
+        // The static routes are skipped when tracing, the index resolves them the same way.
+        if let Option::Some(trace) = trace {
+            trace.clear();
+            return self
+                .tree
+                .lookup_traced(method, path, &self.lookup_options, trace);
+        }
+
+        // A decoded path could match a static segment that the raw path does not.
+        let decoded = self.lookup_options.is_decode_params() && path.contains('%');
+        if let Option::Some(static_routes) = self.static_routes.as_ref().filter(|_| !decoded) {
+            if let Option::Some(name) = static_routes
+                .get(path)
+                .and_then(|methods| methods.get(method))
+            {
+                return Result::Ok((
+                    RouteMatch::create(name, RouteParameter::new()),
+                    Option::None,
+                ));
+            }
+        }
+
+        if self.lookup_options == LookupOptions::default() {
+            self.tree
+                .lookup(method, path)
+                .map(|route_match| (route_match, Option::None))
+        } else {
+            self.tree.lookup_with(method, path, &self.lookup_options)
+        }
+    }
+
+    /// Create a link to a given route and parameters.
+    pub fn link(
+        &self,
+        route_name: &str,
+        route_params: Map<String, String>,
+    ) -> Result<Url, RouterError> {
+        self.link_path(route_name, route_params)
+            .and_then(|rendered| {
+                // The rendered path is joined relative to the base, keeping the path of the base.
+                self.base
+                    .join(rendered.trim_start_matches(PATH_SEPARATOR))
+                    .map_err(RouterError::from)
+            })
+    }
+
+    /// Render the path of a given route with the given parameters, e.g. `/users/42`.
+    ///
+    /// Unlike [`link`](#method.link), the base url is not involved, so neither its path is
+    /// prepended nor the result is normalized as an url.
+    pub fn link_path(
+        &self,
+        route_name: &str,
+        route_params: RouteParameter,
+    ) -> Result<String, RouterError> {
+        self.routes
+            .get(route_name)
+            .ok_or_else(|| RouterError::RouteNotFound {
+                route_name: String::from(route_name),
+            })
+            .and_then(|r| r.get_path().render(route_params).map_err(RouterError::from))
+    }
+
+    /// Create a link to a given route and parameters with a query string.
+    ///
+    /// The query pairs are percent-encoded and appended in the given order.
+    pub fn link_with_query(
+        &self,
+        route_name: &str,
+        route_params: RouteParameter,
+        query: &[(&str, &str)],
+    ) -> Result<Url, RouterError> {
+        self.link(route_name, route_params).map(|mut url| {
+            if !query.is_empty() {
+                url.query_pairs_mut().extend_pairs(query);
+            }
+
+            url
+        })
+    }
+
+    /// Extract the parameters of a named route from a concrete path, the inverse of
+    /// [`link`](#method.link).
+    ///
+    /// The path is matched against the route's path only, without a lookup, so the method and
+    /// the other routes are not taken into account.
+    pub fn extract_params(
+        &self,
+        route_name: &str,
+        path: &str,
+    ) -> Result<RouteParameter, RouterError> {
+        let route = self
+            .routes
+            .get(route_name)
+            .ok_or_else(|| RouterError::RouteNotFound {
+                route_name: String::from(route_name),
+            })?;
+
+        Result::Ok(route.get_path().extract(path)?)
+    }
+
+    /// Return the size statistics of the router, e.g. to catch an accidental growth of the
+    /// routes in tests.
+    ///
+    /// The node statistics are provided by the route index.
+    pub fn stats(&self) -> RouterStats {
+        let mut stats = self.tree.stats();
+        stats.set_route_count(self.routes.len());
+
+        stats
+    }
+
+    /// Tries to compact the memory footprint of the router.
+    ///
+    /// The fully static routes are also collected into a flat map, so they are resolved with a
+    /// single hash lookup before the route index is walked. The map takes memory proportional to
+    /// the static routes, and it is dropped when a route is added or removed, until the router is
+    /// optimized again.
+    pub fn optimize(mut self) -> Self {
+        self.optimize_in_place();
+
+        self
+    }
+
+    /// Same as [`Router::optimize`], but works through a mutable borrow.
+    pub fn optimize_in_place(&mut self) {
+        self.routes.optimize();
+        self.tree.optimize();
+
+        let mut static_routes: Map<String, SmallMap<Method, String>> = Map::new();
+        for (name, route) in self.routes.iter() {
+            for path in route.paths() {
+                if path.is_greedy() || !path.get_items().iter().all(Item::is_static) {
+                    continue;
+                }
+
+                let mut rendered = String::new();
+                for item in path.get_items() {
+                    rendered.push_str(PATH_SEPARATOR);
+                    rendered.push_str(item.get_name());
+                }
+                if rendered.is_empty() {
+                    rendered.push_str(PATH_SEPARATOR);
+                }
+                static_routes
+                    .entry(rendered)
+                    .or_default()
+                    .insert(path.get_method().clone(), name.clone());
+            }
+        }
+        for (_, methods) in static_routes.iter_mut() {
+            methods.optimize();
+        }
+        static_routes.optimize();
+        self.static_routes = Option::Some(static_routes);
+    }
+}
+
+/// The router is serialized as its base url and its routes sorted by name.
+///
+/// The lookup settings are not serialized.
+#[cfg(feature = "serde")]
+impl<T, I> Serialize for Router<T, I>
+where
+    T: Debug + Serialize,
+    I: RouteIndex<String>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut routes: Vec<&Route<T>> = self.routes().collect();
+        routes.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        let mut state = serializer.serialize_struct("Router", 2)?;
+        state.serialize_field("base", self.base.as_str())?;
+        state.serialize_field("routes", &routes)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RouterDefinition<T> {
+    base: String,
+    routes: Vec<Route<T>>,
+}
+
+/// The routes are added one by one, so conflicting routes are reported as errors.
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for Router<T>
+where
+    T: Debug + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let definition = RouterDefinition::deserialize(deserializer)?;
+        let base = Url::parse(&definition.base).map_err(D::Error::custom)?;
+        let mut router = Router::new(base);
+        for route in definition.routes {
+            router.add(route).map_err(D::Error::custom)?;
+        }
+
+        Result::Ok(router)
+    }
+}
+
+/// Routes sharing a path prefix, created by [`Router::scope`](struct.Router.html#method.scope).
+#[derive(Debug)]
+pub struct Scope<'r, T: Debug, I: RouteIndex<String> = Tree<String>> {
+    router: &'r mut Router<T, I>,
+    prefix: Path,
+    added: Vec<String>,
+    replaced: Vec<Route<T>>,
+    error: Option<RouterError>,
+}
+
+impl<'r, T, I> Scope<'r, T, I>
+where
+    T: Debug,
+    I: RouteIndex<String>,
+{
+    /// Add a route with the prefix of the scope prepended to its path.
+    ///
+    /// After the first error the following routes are ignored.
+    pub fn add(&mut self, r: Route<T>) -> &mut Self {
+        if self.error.is_some() {
+            return self;
+        }
+
+        let name = String::from(r.get_name());
+        let result = r
+            .with_prefix(name.clone(), &self.prefix)
+            .map_err(RouterError::from)
+            .and_then(|r| {
+                self.router
+                    .add_displacing(r, &mut self.replaced)
+                    .map(|_| ())
+            });
+
+        match result {
+            Result::Ok(()) => self.added.push(name),
+            Result::Err(err) => self.error = Option::Some(err),
+        }
+
+        self
+    }
+}
+
+/// Collects the settings of a router, created by
+/// [`Router::builder`](struct.Router.html#method.builder).
+#[derive(Debug)]
+pub struct RouterBuilder<T: Debug, I: RouteIndex<String> = Tree<String>> {
+    router: Router<T, I>,
+}
+
+impl<T, I> RouterBuilder<T, I>
+where
+    T: Debug,
+    I: RouteIndex<String>,
+{
+    /// Match the static segments of the paths ignoring their case, see
+    /// [`Router::set_case_insensitive`](struct.Router.html#method.set_case_insensitive).
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.router.set_case_insensitive(case_insensitive);
+        self
+    }
+
+    /// Percent-decode the path segments, see
+    /// [`Router::decode_params`](struct.Router.html#method.decode_params).
+    pub fn decode_params(mut self, decode_params: bool) -> Self {
+        self.router.decode_params(decode_params);
+        self
+    }
+
+    /// Set the trailing slash handling, see
+    /// [`Router::set_trailing_slash`](struct.Router.html#method.set_trailing_slash).
+    pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.router.set_trailing_slash(trailing_slash);
+        self
+    }
+
+    /// Set the dot segment handling, see
+    /// [`Router::set_dot_segments`](struct.Router.html#method.set_dot_segments).
+    pub fn dot_segments(mut self, dot_segments: DotSegments) -> Self {
+        self.router.set_dot_segments(dot_segments);
+        self
+    }
+
+    /// Resolve `HEAD` requests to `GET` routes, see
+    /// [`Router::head_fallback`](struct.Router.html#method.head_fallback).
+    pub fn head_fallback(mut self, head_fallback: bool) -> Self {
+        self.router.head_fallback(head_fallback);
+        self
+    }
+
+    /// Set how conflicting routes are handled, see
+    /// [`Router::set_conflict_policy`](struct.Router.html#method.set_conflict_policy).
+    pub fn conflict_policy(mut self, conflict_policy: ConflictPolicy) -> Self {
+        self.router.set_conflict_policy(conflict_policy);
+        self
+    }
+
+    /// Reject the paths containing an encoded path separator, see
+    /// [`Router::reject_encoded_separators`](struct.Router.html#method.reject_encoded_separators).
+    pub fn reject_encoded_separators(mut self, reject_encoded_separators: bool) -> Self {
+        self.router
+            .reject_encoded_separators(reject_encoded_separators);
+        self
+    }
+
+    /// Limit the number of segments of the resolved paths, see
+    /// [`Router::max_path_depth`](struct.Router.html#method.max_path_depth).
+    pub fn max_path_depth(mut self, limit: usize) -> Self {
+        self.router.max_path_depth(limit);
+        self
+    }
+
+    /// Capture a locale from the first segment, see
+    /// [`Router::set_locale_segment`](struct.Router.html#method.set_locale_segment).
+    pub fn locale_segment(mut self, supported: &[&str]) -> Self {
+        self.router.set_locale_segment(supported);
+        self
+    }
+
+    /// Set the default locale, see
+    /// [`Router::set_default_locale`](struct.Router.html#method.set_default_locale).
+    pub fn default_locale(mut self, locale: &str) -> Self {
+        self.router.set_default_locale(locale);
+        self
+    }
+
+    /// Set a route that is checked before the others, see
+    /// [`Router::set_fast_path`](struct.Router.html#method.set_fast_path).
+    pub fn fast_path(mut self, method: Method, path: &str, item: T) -> Self {
+        self.router.set_fast_path(method, path, item);
+        self
+    }
+
+    /// Set the item of the paths without a route, see
+    /// [`Router::set_fallback`](struct.Router.html#method.set_fallback).
+    pub fn fallback(mut self, item: T) -> Self {
+        self.router.set_fallback(item);
+        self
+    }
+
+    /// Create the router.
+    pub fn build(self) -> Router<T, I> {
+        self.router
+    }
+}
+
+/// Creates a router with the `http://localhost/` base url, meant for tests and prototypes.
+///
+/// The links are generated against the base, e.g. `http://localhost/users/42`, so use
+/// [`Router::new`](struct.Router.html#method.new) with the real base url otherwise.
+impl<T> Default for Router<T>
+where
+    T: Debug,
+{
+    fn default() -> Self {
+        Router::new(Url::parse("http://localhost/").expect("the default base url is valid"))
+    }
+}
+
+impl<T, I> RouteResolver for Router<T, I>
+where
+    T: Debug,
+    I: RouteIndex<String>,
+{
+    type Item = T;
+
+    fn resolve(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<RouteMatch<'_, Self::Item>, RouterError> {
+        self.resolve(method, path)
+    }
+}
+
+impl<T, I> Linker for Router<T, I>
+where
+    T: Debug,
+    I: RouteIndex<String>,
+{
+    fn link(&self, route_name: &str, route_params: RouteParameter) -> Result<Url, RouterError> {
+        self.link(route_name, route_params)
+    }
+}
+
+impl<R> RouteResolver for &R
+where
+    R: RouteResolver + ?Sized,
+{
+    type Item = R::Item;
+
+    fn resolve(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<RouteMatch<'_, Self::Item>, RouterError> {
+        (**self).resolve(method, path)
+    }
+}
+
+impl<R> RouteResolver for Arc<R>
+where
+    R: RouteResolver + ?Sized,
+{
+    type Item = R::Item;
+
+    fn resolve(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Result<RouteMatch<'_, Self::Item>, RouterError> {
+        (**self).resolve(method, path)
+    }
+}
+
+impl<L> Linker for &L
+where
+    L: Linker + ?Sized,
+{
+    fn link(&self, route_name: &str, route_params: RouteParameter) -> Result<Url, RouterError> {
+        (**self).link(route_name, route_params)
+    }
+}
+
+impl<L> Linker for Arc<L>
+where
+    L: Linker + ?Sized,
+{
+    fn link(&self, route_name: &str, route_params: RouteParameter) -> Result<Url, RouterError> {
+        (**self).link(route_name, route_params)
+    }
+}
+
+/// router errors
+#[derive(Error, Debug, PartialEq)]
+pub enum RouterError {
+    /// the route already exists
+    #[error("route already exists")]
+    RouteAlreadyExists {
+        /// already existing route
+        route_name: String,
+    },
+    /// route not found
+    #[error("route not found")]
+    RouteNotFound {
+        /// missing route
+        route_name: String,
+    },
+    /// router tree error
+    #[error("route tree error: {tree_error}")]
+    TreeError {
+        /// tree error
+        #[from]
+        tree_error: TreeError,
+    },
+    /// router path error
+    #[error("route path error: {path_error}")]
+    PathError {
+        /// path error
+        #[from]
+        path_error: PathError,
+    },
+    /// the url does not belong to the base url of the router
+    #[error("url does not match the base url: {url}")]
+    BaseMismatch {
+        /// resolved url
+        url: String,
+    },
+    /// the route has both aliases and a predicate
+    #[error("route with a predicate cannot have aliases: {route_name}")]
+    AliasWithPredicate {
+        /// rejected route
+        route_name: String,
+    },
+    /// the path contains an encoded path separator
+    #[error("encoded path separator in path: {path}")]
+    EncodedSeparator {
+        /// rejected path
+        path: String,
+    },
+    /// the path contains a `.` or `..` segment
+    #[error("dot segment in path: {path}")]
+    DotSegment {
+        /// rejected path
+        path: String,
+    },
+    /// the path has more segments than allowed
+    #[error("path too deep: {depth} segments, the limit is {limit}")]
+    PathTooDeep {
+        /// number of segments of the path
+        depth: usize,
+        /// maximum number of segments
+        limit: usize,
+    },
+    /// url parser error
+    #[error("failed to parse url: {parse_error}")]
+    UrlParseError {
+        /// url parse error
+        #[from]
+        parse_error: ParseError,
+    },
+}
+
+impl RouterError {
+    /// Whether no route was found for the path or the url, the usual `404 Not Found`.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            RouterError::TreeError {
+                tree_error: TreeError::PathNotFound { .. },
+            } | RouterError::BaseMismatch { .. }
+        )
+    }
+
+    /// Whether routes were found for the path but not for the method, the usual
+    /// `405 Method Not Allowed`.
+    pub fn is_method_not_allowed(&self) -> bool {
+        matches!(
+            self,
+            RouterError::TreeError {
+                tree_error: TreeError::MethodNotFound { .. },
+            }
+        )
+    }
+
+    /// Whether the path was rejected by the limits of the router, like
+    /// [`Router::max_path_depth`](struct.Router.html#method.max_path_depth), before
+    /// looking for a route.
+    pub fn is_rejected_path(&self) -> bool {
+        matches!(
+            self,
+            RouterError::EncodedSeparator { .. }
+                | RouterError::DotSegment { .. }
+                | RouterError::PathTooDeep { .. }
+        )
+    }
+
+    /// Return the HTTP status code a server usually responds with for the error: 404 when no
+    /// route was found, 405 when the method is not allowed, 400 when the path was rejected, and
+    /// 500 for the other errors, which are not caused by the request, e.g. a missing route name
+    /// when creating a link.
+    pub fn status_hint(&self) -> u16 {
+        if self.is_not_found() {
+            404
+        } else if self.is_method_not_allowed() {
+            405
+        } else if self.is_rejected_path() {
+            400
+        } else {
+            500
+        }
+    }
+}
+
+/// errors of the [`Router::try_resolve`](struct.Router.html#method.try_resolve) method
+#[derive(Error, Debug, PartialEq)]
+pub enum ResolveError {
+    /// the path matched a route with a different casing
+    #[error("redirect to canonical path: {to}")]
+    CaseRedirect {
+        /// canonical path
+        to: String,
+    },
+    /// router error
+    #[error("router error: {router_error}")]
+    RouterError {
+        /// router error
+        #[from]
+        router_error: RouterError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::DotSegments;
+    use crate::PredicateContext;
+    use crate::TraceBranch;
+    use crate::TraceStep;
+    use crate::TrailingSlash;
+    use crate::{map::Map, Linker};
+    use crate::{ConflictPolicy, ResolveError, Route, RouteResolver, Router, RouterError};
+    use crate::{Path, PathError, RouteIndex, RouteMatch, TreeError, ANY_METHOD, LOCALE_PARAMETER};
+    use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+    use http::Method;
+    use rand::Rng;
+    use rstest::*;
+    use std::sync::Arc;
+    use url::Url;
+
+    #[rstest]
+    fn test_happy_path() {
+        let mut rng = rand::thread_rng();
+
+        let root_item: u64 = rng.gen();
+
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("root", Method::GET, "/", root_item).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("root", Method::GET, "/", root_item).unwrap())
+            .is_err());
+
+        let router = router;
+        let m = &Method::GET;
+
+        {
+            let resolver: Box<dyn RouteResolver<Item = u64>> = Box::new(router.clone());
+            assert_eq!(resolver.resolve(m, "/").unwrap().get_item(), &root_item);
+        }
+
+        {
+            let linker: Box<dyn Linker> = Box::new(router.clone());
+            assert_eq!(
+                linker.link("root", Map::new()).unwrap().to_string(),
+                "http://example.com/"
+            );
+            assert!(linker.link("asdf", Map::new()).is_err());
+        }
 
         assert!(router.resolve(m, "/asdf").is_err());
+        assert_eq!(
+            router.resolve(&Method::POST, "/").unwrap_err(),
+            RouterError::TreeError {
+                tree_error: TreeError::MethodNotFound {
+                    method: Method::POST,
+                    allowed: vec![Method::GET],
+                }
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_shared_resolver() {
+        fn resolve_root(resolver: impl RouteResolver<Item = u64> + Linker) -> (u64, String) {
+            (
+                *resolver.resolve(&Method::GET, "/").unwrap().get_item(),
+                resolver.link("root", Map::new()).unwrap().to_string(),
+            )
+        }
+
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        assert!(router
+            .add(Route::create("root", Method::GET, "/", 1).unwrap())
+            .is_ok());
+
+        let expected = (1, String::from("http://example.com/"));
+        assert_eq!(resolve_root(&router), expected);
+
+        let shared = Arc::new(router);
+        assert_eq!(resolve_root(shared.clone()), expected);
+        assert_eq!(resolve_root(&shared), expected);
+    }
+
+    #[rstest]
+    fn test_trailing_slash_parameter() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("page", Method::GET, "/page", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("page_slash", Method::GET, "/page/:trailing?", 2).unwrap())
+            .is_ok());
+
+        let router = router;
+        let m = &Method::GET;
+
+        assert_eq!(router.resolve(m, "/page").unwrap().get_item(), &1);
+
+        let route_match = router.resolve(m, "/page/").unwrap();
+        assert_eq!(route_match.get_item(), &2);
+        assert_eq!(route_match.get_params().get("trailing").unwrap(), "");
+
+        assert_eq!(
+            router
+                .link("page_slash", route_match.move_params())
+                .unwrap()
+                .to_string(),
+            "http://example.com/page/"
+        );
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct StaticIndex {
+        items: Map<String, String>,
+    }
+
+    impl RouteIndex<String> for StaticIndex {
+        fn add_with(
+            &mut self,
+            path: Path,
+            item: String,
+            policy: ConflictPolicy,
+        ) -> Result<Vec<String>, TreeError> {
+            let key = path.to_string();
+            if !self.items.contains_key(&key) {
+                self.items.insert(key, item);
+                return Result::Ok(Vec::new());
+            }
+
+            match policy {
+                ConflictPolicy::Reject => {
+                    Result::Err(TreeError::PathAlreadyRegistered { route: key })
+                }
+                ConflictPolicy::ReplaceExisting => {
+                    Result::Ok(self.items.insert(key, item).into_iter().collect())
+                }
+                ConflictPolicy::KeepExisting => Result::Ok(vec![item]),
+            }
+        }
+
+        fn remove(&mut self, path: &Path) -> Result<String, TreeError> {
+            let key = path.to_string();
+            self.items
+                .remove(&key)
+                .ok_or(TreeError::PathNotFound { path: key })
+        }
+
+        fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, String>, TreeError> {
+            self.items
+                .get(&format!("{} {}", method, path))
+                .map(|item| RouteMatch::create(item, Map::new()))
+                .ok_or_else(|| TreeError::PathNotFound {
+                    path: String::from(path),
+                })
+        }
+
+        fn clear(&mut self) {
+            self.items = Map::new();
+        }
+
+        fn optimize(&mut self) {
+            self.items.optimize();
+        }
+    }
+
+    #[rstest]
+    fn test_custom_index() {
+        let mut router = Router::with_index(
+            Url::parse("http://example.com").unwrap(),
+            StaticIndex::default(),
+        );
+
+        assert!(router
+            .add(Route::create("about", Method::GET, "/about", 1).unwrap())
+            .is_ok());
+
+        let router = router.optimize();
+        let m = &Method::GET;
+
+        assert_eq!(router.resolve(m, "/about").unwrap().get_item(), &1);
+        assert!(router.resolve(m, "/contact").is_err());
+        assert!(router.matches(m, "/about"));
+        assert!(!router.matches(m, "/contact"));
+        assert_eq!(router.allowed_methods("/about").unwrap(), vec![Method::GET]);
+        assert!(router.allowed_methods("/contact").is_err());
+        assert_eq!(
+            router.link("about", Map::new()).unwrap().to_string(),
+            "http://example.com/about"
+        );
+
+        let mut router = router;
+        router.clear();
+        assert!(router.resolve(m, "/about").is_err());
+        assert!(router.route("about").is_none());
+        assert!(router
+            .add(Route::create("about", Method::GET, "/about", 2).unwrap())
+            .is_ok());
+        assert_eq!(router.resolve(m, "/about").unwrap().get_item(), &2);
+
+        router.set_conflict_policy(ConflictPolicy::KeepExisting);
+        assert!(router
+            .add(Route::create("about.kept", Method::GET, "/about", 3).unwrap())
+            .is_ok());
+        assert!(router.route("about.kept").is_none());
+        assert_eq!(router.resolve(m, "/about").unwrap().get_item(), &2);
+
+        router.set_conflict_policy(ConflictPolicy::ReplaceExisting);
+        assert!(router
+            .add(Route::create("about.new", Method::GET, "/about", 4).unwrap())
+            .is_ok());
+        assert!(router.route("about").is_none());
+        assert_eq!(router.resolve(m, "/about").unwrap().get_item(), &4);
+    }
+
+    #[rstest]
+    fn test_remove_index_failure() {
+        let mut router = Router::with_index(
+            Url::parse("http://example.com").unwrap(),
+            StaticIndex::default(),
+        );
+        assert!(router
+            .add(
+                Route::create("about", Method::GET, "/about", 1)
+                    .unwrap()
+                    .with_alias("/info")
+                    .unwrap()
+            )
+            .is_ok());
+        assert!(router
+            .tree
+            .remove(&Path::parse(Method::GET, "/info").unwrap())
+            .is_ok());
+
+        assert!(router.remove("about").is_err());
+        assert!(router.route("about").is_some());
+        assert_eq!(
+            router.resolve(&Method::GET, "/about").unwrap().get_item(),
+            &1
+        );
+    }
+
+    #[rstest]
+    fn test_default_locale() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("about", Method::GET, "/about", 1).unwrap())
+            .is_ok());
+        router.set_locale_segment(&["en", "hu"]);
+
+        let m = &Method::GET;
+
+        assert_eq!(
+            router
+                .resolve(m, "/hu/about")
+                .unwrap()
+                .get_params()
+                .get(LOCALE_PARAMETER)
+                .unwrap(),
+            "hu"
+        );
+        assert!(router.resolve(m, "/fr/about").is_err());
+        assert!(router.resolve(m, "/about").is_err());
+
+        router.set_default_locale("en");
+
+        let route_match = router.resolve(m, "/about").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(
+            route_match.get_params().get(LOCALE_PARAMETER).unwrap(),
+            "en"
+        );
+        assert!(router.resolve(m, "/fr/about").is_err());
+    }
+
+    #[rstest]
+    fn test_head_fallback() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("health", Method::GET, "/health", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("page", Method::GET, "/page", 2).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("page.head", Method::HEAD, "/page", 3).unwrap())
+            .is_ok());
+
+        assert!(router.resolve(&Method::HEAD, "/health").is_err());
+
+        router.head_fallback(true);
+
+        let route_match = router.resolve(&Method::HEAD, "/health").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert!(route_match.is_head_fallback());
+
+        let route_match = router.resolve(&Method::HEAD, "/page").unwrap();
+        assert_eq!(route_match.get_item(), &3);
+        assert!(!route_match.is_head_fallback());
+
+        assert!(!router
+            .resolve(&Method::GET, "/health")
+            .unwrap()
+            .is_head_fallback());
+        assert!(router.resolve(&Method::POST, "/health").is_err());
+        assert!(router.resolve(&Method::HEAD, "/missing").is_err());
+    }
+
+    #[rstest]
+    fn test_multiple_methods() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        for route in
+            Route::create_multi("search", &[Method::GET, Method::POST], "/search", 1).unwrap()
+        {
+            assert!(router.add(route).is_ok());
+        }
+
+        assert_eq!(
+            router.resolve(&Method::GET, "/search").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(
+            router
+                .resolve(&Method::POST, "/search")
+                .unwrap()
+                .get_route_name(),
+            Option::Some("search.post")
+        );
+        assert!(router.resolve(&Method::PUT, "/search").is_err());
+    }
+
+    #[rstest]
+    fn test_matches() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 2);
+
+        assert!(router.matches(&Method::GET, "/users/5"));
+        assert!(router.matches(&Method::GET, "/health"));
+        assert!(!router.matches(&Method::POST, "/users/5"));
+        assert!(!router.matches(&Method::HEAD, "/users/5"));
+        assert!(!router.matches(&Method::GET, "/users"));
+
+        router.head_fallback(true);
+        router.set_locale_segment(&["en"]);
+
+        assert!(router.matches(&Method::HEAD, "/en/users/5"));
+        assert!(!router.matches(&Method::GET, "/users/5"));
+        assert!(!router.matches(&Method::GET, "/fr/users/5"));
+    }
+
+    #[rstest]
+    fn test_allowed_methods() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("users", Method::GET, "/users", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("users.create", Method::POST, "/users", 2).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("user.update", Method::PUT, "/users/:id", 3).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("files", Method::GET, "/files/*path", 4).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 5);
+
+        assert_eq!(
+            router.allowed_methods("/users").unwrap(),
+            vec![Method::GET, Method::POST]
+        );
+        assert_eq!(
+            router.allowed_methods("/users/42").unwrap(),
+            vec![Method::PUT]
+        );
+        assert_eq!(
+            router.allowed_methods("/files/a/b").unwrap(),
+            vec![Method::GET]
+        );
+        assert_eq!(
+            router.allowed_methods("/health").unwrap(),
+            vec![Method::GET]
+        );
+        assert!(router.allowed_methods("/").is_err());
+        assert!(router.allowed_methods("/users/42/posts").is_err());
+
+        router.head_fallback(true);
+
+        assert_eq!(
+            router.allowed_methods("/users").unwrap(),
+            vec![Method::GET, Method::HEAD, Method::POST]
+        );
+    }
+
+    #[rstest]
+    fn test_resolve_mut() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 0).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 0);
+
+        for _ in 0..3 {
+            let (counter, params) = router.resolve_mut(&Method::GET, "/users/5").unwrap();
+            *counter += 1;
+            assert_eq!(params.get("id").unwrap(), "5");
+        }
+        *router.resolve_mut(&Method::GET, "/health").unwrap().0 += 10;
+
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/6").unwrap().get_item(),
+            &3
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/health").unwrap().get_item(),
+            &10
+        );
+        assert!(router.resolve_mut(&Method::POST, "/users/5").is_err());
+    }
+
+    #[rstest]
+    fn test_optimize_in_place() {
+        struct App {
+            router: Router<i32>,
+        }
+
+        let mut app = App {
+            router: Router::new(Url::parse("http://example.com").unwrap()),
+        };
+
+        assert!(app
+            .router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        app.router.optimize_in_place();
+
+        assert_eq!(
+            app.router
+                .resolve(&Method::GET, "/users/5")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+    }
+
+    #[rstest]
+    fn test_resolve_route_name() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user.show", Method::GET, "/user/:id", 1).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 2);
+
+        let route_match = router.resolve(&Method::GET, "/user/42").unwrap();
+        assert_eq!(route_match.get_route_name(), Option::Some("user.show"));
+        assert_eq!(route_match.pattern(), Option::Some("/user/:id"));
+
+        let route_match = router.resolve(&Method::GET, "/health").unwrap();
+        assert_eq!(route_match.get_route_name(), Option::None);
+        assert_eq!(route_match.pattern(), Option::None);
+    }
+
+    #[rstest]
+    fn test_resolve_meta() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(
+                Route::create("admin", Method::GET, "/admin/:page", 1)
+                    .unwrap()
+                    .with_meta("auth", "admin")
+            )
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 2);
+
+        let route_match = router.resolve(&Method::GET, "/admin/users").unwrap();
+        assert_eq!(
+            route_match.get_meta().unwrap().get("auth").unwrap(),
+            "admin"
+        );
+
+        let route_match = router.resolve(&Method::GET, "/health").unwrap();
+        assert!(route_match.get_meta().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_serde() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("index", Method::GET, "/", 2).unwrap())
+            .is_ok());
+
+        let encoded = serde_json::to_string(&router).unwrap();
+        assert_eq!(
+            encoded,
+            concat!(
+                r#"{"base":"http://example.com/","routes":["#,
+                r#"{"name":"index","method":"GET","path":"/","greedy":false,"item":2},"#,
+                r#"{"name":"user","method":"GET","path":"/users/:id","greedy":false,"item":1}"#,
+                r#"]}"#
+            )
+        );
+
+        let decoded: Router<u64> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(
+            decoded
+                .resolve(&Method::GET, "/users/5")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), encoded);
+
+        let conflicting = concat!(
+            r#"{"base":"http://example.com/","routes":["#,
+            r#"{"name":"a","method":"GET","path":"/a","item":1},"#,
+            r#"{"name":"b","method":"GET","path":"/a","item":2}"#,
+            r#"]}"#
+        );
+        assert!(serde_json::from_str::<Router<u64>>(conflicting).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_route_match_summary_round_trip() {
+        use crate::RouteMatchSummary;
+
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/user/:id", 1).unwrap())
+            .is_ok());
+
+        let route_match = router.resolve(&Method::GET, "/user/42").unwrap();
+        let summary = RouteMatchSummary::from(&route_match);
+        assert_eq!(summary.get_route_name(), Option::Some("user"));
+        assert_eq!(summary.get_params().get("id").unwrap(), "42");
+
+        let encoded = serde_json::to_string(&summary).unwrap();
+        let decoded: RouteMatchSummary = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, summary);
+    }
+
+    #[rstest]
+    fn test_fast_path() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("healthz", Method::GET, "/healthz", 1).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/healthz", 2);
+
+        let m = &Method::GET;
+
+        assert_eq!(router.resolve(m, "/healthz").unwrap().get_item(), &2);
+        assert_eq!(router.resolve(m, "/healthz/").unwrap().get_item(), &1);
+        assert!(router.resolve(&Method::POST, "/healthz").is_err());
+    }
+
+    #[rstest]
+    fn test_case_redirect() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        router.set_locale_segment(&["en"]).set_default_locale("en");
+
+        let m = &Method::GET;
+
+        assert!(router.resolve(m, "/Users/AbC").is_err());
+        assert!(router.try_resolve(m, "/Users/AbC").is_err());
+
+        router.set_case_insensitive(true);
+
+        assert_eq!(router.resolve(m, "/Users/AbC").unwrap().get_item(), &1);
+        assert_eq!(router.try_resolve(m, "/users/AbC").unwrap().get_item(), &1);
+        assert_eq!(
+            router.try_resolve(m, "/Users/AbC").unwrap_err(),
+            ResolveError::CaseRedirect {
+                to: String::from("/users/AbC")
+            }
+        );
+        assert_eq!(
+            router.try_resolve(m, "/en/USERS/AbC").unwrap_err(),
+            ResolveError::CaseRedirect {
+                to: String::from("/en/users/AbC")
+            }
+        );
+        assert!(router.try_resolve(m, "/posts/AbC").is_err());
+    }
+
+    #[rstest]
+    fn test_link_with_base_path() {
+        let mut router = Router::new(Url::parse("http://example.com/app/").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("index", Method::GET, "/", 2).unwrap())
+            .is_ok());
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+
+        assert_eq!(
+            router.link("user", params).unwrap().to_string(),
+            "http://example.com/app/users/5"
+        );
+        assert_eq!(
+            router.link("index", Map::new()).unwrap().to_string(),
+            "http://example.com/app/"
+        );
+    }
+
+    #[rstest]
+    fn test_error_source() {
+        use std::error::Error;
+
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+
+        let err = router.try_resolve(&Method::GET, "/posts").unwrap_err();
+        let router_error = err.source().unwrap();
+        assert!(router_error.is::<RouterError>());
+        assert_eq!(
+            router_error.source().unwrap().downcast_ref::<TreeError>(),
+            Option::Some(&TreeError::PathNotFound {
+                path: String::from("/posts")
+            })
+        );
+
+        let err = router.link("user", Map::new()).unwrap_err();
+        assert!(err.source().unwrap().is::<PathError>());
+    }
+
+    #[rstest]
+    fn test_fallback() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        assert!(router.resolve(&Method::GET, "/posts").is_err());
+
+        router.set_fallback(404);
+
+        let route_match = router.resolve(&Method::GET, "/posts").unwrap();
+        assert_eq!(route_match.get_item(), &404);
+        assert!(route_match.get_params().is_empty());
+        assert!(route_match.get_route_name().is_none());
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/5").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(
+            router.resolve(&Method::POST, "/users/5").unwrap_err(),
+            RouterError::TreeError {
+                tree_error: TreeError::MethodNotFound {
+                    method: Method::POST,
+                    allowed: vec![Method::GET],
+                }
+            }
+        );
+        assert!(!router.matches(&Method::GET, "/posts"));
+
+        *router.resolve_mut(&Method::GET, "/posts").unwrap().0 += 1;
+        assert_eq!(
+            router.resolve(&Method::GET, "/posts").unwrap().get_item(),
+            &405
+        );
+    }
+
+    #[rstest]
+    fn test_try_from_routes() {
+        let base = Url::parse("http://example.com").unwrap();
+        let router = Router::try_from_routes(
+            base.clone(),
+            vec![
+                Route::create("user", Method::GET, "/users/:id", 1).unwrap(),
+                Route::create("index", Method::GET, "/", 2).unwrap(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/5").unwrap().get_item(),
+            &1
+        );
+
+        let errors = Router::try_from_routes(
+            base.clone(),
+            vec![
+                Route::create("user", Method::GET, "/users/:id", 1).unwrap(),
+                Route::create("user", Method::GET, "/people/:id", 2).unwrap(),
+                Route::create("index", Method::GET, "/", 3).unwrap(),
+                Route::create("profile", Method::GET, "/users/:name", 4).unwrap(),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                RouterError::RouteAlreadyExists {
+                    route_name: String::from("user")
+                },
+                RouterError::TreeError {
+                    tree_error: TreeError::ConflictingDynamicSegment {
+                        existing: String::from(":id"),
+                        incoming: String::from(":name"),
+                    }
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[should_panic]
+    fn test_from_routes_panics() {
+        Router::from_routes(
+            Url::parse("http://example.com").unwrap(),
+            vec![
+                Route::create("a", Method::GET, "/a", 1).unwrap(),
+                Route::create("b", Method::GET, "/a", 2).unwrap(),
+            ],
+        );
+    }
+
+    #[rstest]
+    fn test_extract_params() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("post", Method::GET, "/users/:id/posts/:post", 1).unwrap())
+            .is_ok());
+
+        let params = router.extract_params("post", "/users/42/posts/7").unwrap();
+        assert_eq!(params.get("id").unwrap(), "42");
+        assert_eq!(params.get("post").unwrap(), "7");
+        assert_eq!(
+            router.link("post", params).unwrap().to_string(),
+            "http://example.com/users/42/posts/7"
+        );
+
+        assert_eq!(
+            router.extract_params("user", "/users/42").unwrap_err(),
+            RouterError::RouteNotFound {
+                route_name: String::from("user")
+            }
+        );
+        assert_eq!(
+            router.extract_params("post", "/users/42").unwrap_err(),
+            RouterError::PathError {
+                path_error: PathError::PathMismatch {
+                    path: String::from("/users/42"),
+                    index: 2
+                }
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_stats() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("users", Method::GET, "/users", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 2).unwrap())
+            .is_ok());
+
+        let stats = router.stats();
+        assert_eq!(stats.get_route_count(), 2);
+        assert_eq!(stats.get_node_count(), 3);
+        assert_eq!(stats.get_max_depth(), 2);
+        assert_eq!(stats.get_dynamic_node_count(), 1);
+    }
+
+    #[rstest]
+    fn test_add_any_method() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router.add_any_method("proxy", "/proxy/*rest", 1).is_ok());
+        assert!(router
+            .add(Route::create("proxy.get", Method::GET, "/proxy/*rest", 2).unwrap())
+            .is_ok());
+        assert!(router.add_any_method("proxy", "/other", 3).is_err());
+        assert!(router.add_any_method("invalid", "/*a/b", 3).is_err());
+
+        let route_match = router.resolve(&Method::POST, "/proxy/a/b").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(route_match.get_route_name(), Option::Some("proxy"));
+        assert_eq!(
+            router.resolve(&Method::GET, "/proxy/a").unwrap().get_item(),
+            &2
+        );
+        assert!(router.matches(&Method::DELETE, "/proxy/a"));
+        assert_eq!(
+            router.allowed_methods("/proxy/a").unwrap(),
+            vec![
+                Method::CONNECT,
+                Method::DELETE,
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PATCH,
+                Method::POST,
+                Method::PUT,
+                Method::TRACE,
+            ]
+        );
+        assert_eq!(
+            router
+                .link("proxy", {
+                    let mut params = Map::new();
+                    params.insert(String::from("rest"), String::from("a/b"));
+                    params
+                })
+                .unwrap()
+                .to_string(),
+            "http://example.com/proxy/a/b"
+        );
+    }
+
+    #[rstest]
+    fn test_method_case() {
+        let method = |name: &str| Method::from_bytes(name.as_bytes()).unwrap();
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("cache.purge", method("purge"), "/cache", 1).unwrap())
+            .unwrap()
+            .add(Route::create("cache.show", Method::GET, "/cache", 2).unwrap())
+            .unwrap();
+
+        assert!(router
+            .add(Route::create("cache.other", method("PURGE"), "/cache", 3).unwrap())
+            .is_err());
+        assert_eq!(
+            router
+                .resolve(&method("PURGE"), "/cache")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert_eq!(
+            router
+                .resolve(&method("Purge"), "/cache")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert_eq!(
+            router.resolve(&method("get"), "/cache").unwrap().get_item(),
+            &2
+        );
+        assert!(router.matches(&method("get"), "/cache"));
+        assert_eq!(
+            router.allowed_methods("/cache").unwrap(),
+            vec![Method::GET, method("PURGE")]
+        );
+        assert_eq!(router.routes_for_method(&method("purge")).len(), 1);
+    }
+
+    #[rstest]
+    fn test_resolve_full() {
+        let mut router = Router::new(Url::parse("https://example.com/app/").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("index", Method::GET, "/", 2).unwrap())
+            .is_ok());
+
+        let resolve = |url: &str| router.resolve_full(&Method::GET, &Url::parse(url).unwrap());
+
+        let route_match = resolve("https://example.com/app/users/5?tab=posts").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(route_match.get_params().get("id").unwrap(), "5");
+        assert_eq!(resolve("https://example.com/app").unwrap().get_item(), &2);
+        assert_eq!(resolve("https://example.com/app/").unwrap().get_item(), &2);
+        assert_eq!(
+            resolve("https://example.com:443/app/users/5")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert!(matches!(
+            resolve("https://example.com/app/posts").unwrap_err(),
+            RouterError::TreeError { .. }
+        ));
+        for url in [
+            "http://example.com/app/users/5",
+            "https://other.com/app/users/5",
+            "https://example.com:8443/app/users/5",
+            "https://example.com/users/5",
+            "https://example.com/application/users/5",
+        ] {
+            assert_eq!(
+                resolve(url).unwrap_err(),
+                RouterError::BaseMismatch {
+                    url: String::from(url)
+                }
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_path_exists() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        router.set_fast_path(Method::GET, "/health", 2);
+        router.set_fallback(3);
+
+        assert!(router.path_exists("/users/5"));
+        assert!(router.path_exists("/health"));
+        assert!(!router.path_exists("/users"));
+        assert!(!router.path_exists("/posts/5"));
+        assert!(router.path_exists("/users/5") && !router.matches(&Method::POST, "/users/5"));
+
+        assert!(router
+            .add(Route::create("files", Method::GET, "/files/*rest", 4).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("file", Method::POST, "/files/:name", 5).unwrap())
+            .is_ok());
+        assert!(router.path_exists("/files/a/b"));
+        assert_eq!(
+            router.allowed_methods("/files/a/b").unwrap(),
+            vec![Method::GET]
+        );
+    }
+
+    #[rstest]
+    fn test_builder() {
+        let mut router = Router::builder(Url::parse("http://example.com").unwrap())
+            .case_insensitive(true)
+            .decode_params(true)
+            .trailing_slash(TrailingSlash::Relaxed)
+            .head_fallback(true)
+            .locale_segment(&["en", "de"])
+            .default_locale("en")
+            .fast_path(Method::GET, "/health", 0)
+            .fallback(404)
+            .build();
+
+        assert!(router
+            .add(Route::create("file", Method::GET, "/Files/:name", 1).unwrap())
+            .is_ok());
+
+        let route_match = router
+            .resolve(&Method::HEAD, "/de/files/my%20file/")
+            .unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert!(route_match.is_head_fallback());
+        assert_eq!(route_match.get_params().get("name").unwrap(), "my file");
+        assert_eq!(
+            route_match.get_params().get(LOCALE_PARAMETER).unwrap(),
+            "de"
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/health").unwrap().get_item(),
+            &0
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/posts").unwrap().get_item(),
+            &404
+        );
+    }
+
+    #[rstest]
+    fn test_conflict_policy() {
+        let mut router = Router::builder(Url::parse("http://example.com").unwrap())
+            .conflict_policy(ConflictPolicy::ReplaceExisting)
+            .build();
+        router
+            .add(Route::create("users.show", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap()
+            .add(Route::create("users.posts", Method::GET, "/users/:id/posts", 2).unwrap())
+            .unwrap()
+            .add(Route::create("plugin.show", Method::GET, "/users/:name", 3).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/5").unwrap().get_item(),
+            &3
+        );
+        assert!(router.route("users.show").is_none());
+        assert!(router.route("users.posts").is_none());
+        assert!(router.remove("plugin.show").is_ok());
+
+        router.set_conflict_policy(ConflictPolicy::KeepExisting);
+        router
+            .add(Route::create("users.show", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap()
+            .add(Route::create("plugin.show", Method::GET, "/users/:id", 3).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/5").unwrap().get_item(),
+            &1
+        );
+        assert!(router.route("plugin.show").is_none());
+        assert!(router.remove("users.show").is_ok());
+        assert!(router.resolve(&Method::GET, "/users/5").is_err());
+    }
+
+    #[rstest]
+    fn test_resolve_batch() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("users.show", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap()
+            .add(Route::create("users.create", Method::POST, "/users", 2).unwrap())
+            .unwrap();
+
+        let results = router.resolve_batch(&[
+            (Method::GET, "/users/5"),
+            (Method::GET, "/posts"),
+            (Method::POST, "/users"),
+        ]);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().get_item(), &1);
+        assert_eq!(
+            results[0].as_ref().unwrap().get_params().get("id").unwrap(),
+            "5"
+        );
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().get_item(), &2);
+        assert!(router.resolve_batch(&[]).is_empty());
+        #[cfg(feature = "rayon")]
+        {
+            let par_results = router.par_resolve_batch(&[
+                (Method::GET, "/users/5"),
+                (Method::GET, "/posts"),
+                (Method::POST, "/users"),
+            ]);
+            assert_eq!(par_results.len(), 3);
+            assert_eq!(par_results[0].as_ref().unwrap().get_item(), &1);
+            assert!(par_results[1].is_err());
+            assert_eq!(par_results[2].as_ref().unwrap().get_item(), &2);
+        }
+    }
+
+    #[rstest]
+    fn test_walk() {
+        let router = Router::from_routes(
+            Url::parse("http://example.com").unwrap(),
+            vec![
+                Route::create("users.show", Method::GET, "/users/:id", 1).unwrap(),
+                Route::create("users.delete", Method::DELETE, "/users/:id", 2).unwrap(),
+                Route::create("users", Method::GET, "/users", 3).unwrap(),
+            ],
+        );
+
+        let mut visited: Vec<String> = Vec::new();
+        router.walk(|pattern, methods| {
+            let methods: Vec<&str> = methods.iter().map(Method::as_str).collect();
+            visited.push(format!("{} {}", methods.join(","), pattern));
+        });
+
+        assert_eq!(visited, vec!["GET /users", "DELETE,GET /users/:id"]);
+    }
+
+    #[rstest(path, case("/files/foo%2Fbar"), case("/files/foo%2fbar"))]
+    fn test_reject_encoded_separators(path: &str) {
+        let mut router = Router::builder(Url::parse("http://example.com").unwrap())
+            .decode_params(true)
+            .fallback(0)
+            .build();
+        router
+            .add(Route::create("file", Method::GET, "/files/:name", 1).unwrap())
+            .unwrap();
+
+        let route_match = router.resolve(&Method::GET, path).unwrap();
+        assert_eq!(route_match.get_params().get("name").unwrap(), "foo/bar");
+
+        router.reject_encoded_separators(true);
+
+        assert_eq!(
+            router.resolve(&Method::GET, path).unwrap_err(),
+            RouterError::EncodedSeparator {
+                path: String::from(path)
+            }
+        );
+        assert!(router.resolve_mut(&Method::GET, path).is_err());
+        assert!(!router.matches(&Method::GET, path));
+        assert!(router.allowed_methods(path).is_err());
+        assert_eq!(
+            router
+                .resolve(&Method::GET, "/files/foo%20bar")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+    }
+
+    #[rstest]
+    fn test_routes_under() {
+        let router = Router::from_routes(
+            Url::parse("http://example.com").unwrap(),
+            vec![
+                Route::create("users.show", Method::GET, "/api/users/:id", 1).unwrap(),
+                Route::create("posts", Method::GET, "/api/posts", 2).unwrap(),
+                Route::create("home", Method::GET, "/", 3).unwrap(),
+            ],
+        );
+
+        let names = |prefix| {
+            router
+                .routes_under(prefix)
+                .into_iter()
+                .map(Route::get_name)
+                .collect::<Vec<&str>>()
+        };
+
+        assert_eq!(names("/api"), vec!["posts", "users.show"]);
+        assert_eq!(names("/api/users"), vec!["users.show"]);
+        assert_eq!(names("/"), vec!["home", "posts", "users.show"]);
+        assert!(names("/admin").is_empty());
+    }
+
+    #[rstest]
+    fn test_routes_for_method() {
+        let mut router = Router::from_routes(
+            Url::parse("http://example.com").unwrap(),
+            vec![
+                Route::create("users.delete", Method::DELETE, "/users/:id", 1).unwrap(),
+                Route::create("users.show", Method::GET, "/users/:id", 2).unwrap(),
+                Route::create("posts.delete", Method::DELETE, "/posts/:id", 3).unwrap(),
+            ],
+        );
+        router.add_any_method("proxy", "/proxy", 4).unwrap();
+
+        let names = |method: &Method| {
+            router
+                .routes_for_method(method)
+                .into_iter()
+                .map(Route::get_name)
+                .collect::<Vec<&str>>()
+        };
+
+        assert_eq!(names(&Method::DELETE), vec!["posts.delete", "users.delete"]);
+        assert_eq!(names(&Method::GET), vec!["users.show"]);
+        assert!(names(&Method::PUT).is_empty());
+        assert_eq!(
+            names(&Method::from_bytes(ANY_METHOD.as_bytes()).unwrap()),
+            vec!["proxy"]
+        );
+    }
+
+    #[rstest]
+    fn test_add_line() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add_line("GET /users/:id", 1)
+            .unwrap()
+            .add_line("DELETE   /users/:id", 2)
+            .unwrap();
+
+        assert_eq!(
+            router
+                .resolve(&Method::DELETE, "/users/5")
+                .unwrap()
+                .get_item(),
+            &2
+        );
+        assert_eq!(
+            router.route("GET /users/:id").unwrap().get_path(),
+            &Path::parse_get("/users/:id").unwrap()
+        );
+        assert_eq!(
+            router.add_line("/users", 3).unwrap_err(),
+            RouterError::PathError {
+                path_error: PathError::InvalidMethod {
+                    method: String::from("/users")
+                }
+            }
+        );
+        assert!(router.add_line("GET /users/:id", 3).is_err());
+    }
+
+    #[rstest]
+    fn test_max_path_depth() {
+        let mut router = Router::builder(Url::parse("http://example.com").unwrap())
+            .max_path_depth(3)
+            .fallback(0)
+            .build();
+        router
+            .add(Route::create("files", Method::GET, "/files/*path", 1).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router
+                .resolve(&Method::GET, "/files/a/b/")
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/files/a/b/c/d").unwrap_err(),
+            RouterError::PathTooDeep { depth: 5, limit: 3 }
+        );
+        assert!(router.resolve_mut(&Method::GET, "/files/a/b/c").is_err());
+        assert!(!router.matches(&Method::GET, "/files/a/b/c"));
+        assert!(router.allowed_methods("/files/a/b/c").is_err());
+        assert_eq!(
+            router.resolve(&Method::GET, "/a/b/c").unwrap().get_item(),
+            &0
+        );
+    }
+
+    #[rstest]
+    fn test_non_clone_item() {
+        #[derive(Debug)]
+        struct Pool {
+            size: usize,
+        }
+
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("users", Method::GET, "/users", Pool { size: 4 }).unwrap())
+            .unwrap();
+        router.set_fallback(Pool { size: 0 });
+
+        assert_eq!(
+            router
+                .resolve(&Method::GET, "/users")
+                .unwrap()
+                .get_item()
+                .size,
+            4
+        );
+        router.resolve_mut(&Method::GET, "/users").unwrap().0.size = 8;
+        assert_eq!(router.remove("users").unwrap().get_item().size, 8);
+        assert_eq!(
+            router
+                .resolve(&Method::GET, "/users")
+                .unwrap()
+                .get_item()
+                .size,
+            0
+        );
+    }
+
+    #[rstest]
+    fn test_link_path() {
+        let mut router = Router::new(Url::parse("http://example.com/app/").unwrap());
+        router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap();
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("42"));
+
+        assert_eq!(
+            router.link_path("user", params.clone()).unwrap(),
+            "/users/42"
+        );
+        assert_eq!(
+            router.link("user", params.clone()).unwrap().as_str(),
+            "http://example.com/app/users/42"
+        );
+        assert_eq!(
+            router.link_path("missing", params).unwrap_err(),
+            RouterError::RouteNotFound {
+                route_name: String::from("missing")
+            }
+        );
+        assert!(router.link_path("user", Map::new()).is_err());
+    }
+
+    #[rstest]
+    fn test_default() {
+        let mut router = Router::<u64>::default();
+        router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap();
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("42"));
+
+        assert_eq!(router.base().as_str(), "http://localhost/");
+        assert_eq!(
+            router.link("user", params).unwrap().as_str(),
+            "http://localhost/users/42"
+        );
+    }
+
+    #[rstest]
+    fn test_resolve_owned() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("user", Method::GET, "/users/:id", String::from("user")).unwrap())
+            .unwrap();
+
+        let owned = router.resolve_owned(&Method::GET, "/users/42").unwrap();
+        drop(router);
+
+        assert_eq!(owned.get_item(), "user");
+        assert_eq!(owned.param_str("id"), Option::Some("42"));
+        assert_eq!(owned.get_route_name(), Option::Some("user"));
+        assert_eq!(owned.pattern(), Option::Some("/users/:id"));
+    }
+
+    #[rstest]
+    fn test_optimize_static_routes() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("root", Method::GET, "/", 1).unwrap())
+            .unwrap()
+            .add(Route::create("users", Method::GET, "/users", 2).unwrap())
+            .unwrap()
+            .add(Route::create("create_user", Method::POST, "/users", 3).unwrap())
+            .unwrap()
+            .add(Route::create("user", Method::GET, "/users/:id", 4).unwrap())
+            .unwrap()
+            .add(Route::create("escaped", Method::GET, "/a/\\:b", 5).unwrap())
+            .unwrap();
+        router.head_fallback(true);
+        let mut router = router.optimize();
+
+        let route_match = router.resolve(&Method::GET, "/users").unwrap();
+        assert_eq!(route_match.get_item(), &2);
+        assert_eq!(route_match.get_route_name(), Option::Some("users"));
+        assert_eq!(route_match.pattern(), Option::Some("/users"));
+        assert_eq!(
+            router.resolve(&Method::POST, "/users").unwrap().get_item(),
+            &3
+        );
+        assert_eq!(router.resolve(&Method::GET, "/").unwrap().get_item(), &1);
+        assert_eq!(
+            router.resolve(&Method::GET, "/a/:b").unwrap().get_item(),
+            &5
+        );
+        assert!(router
+            .resolve(&Method::HEAD, "/users")
+            .unwrap()
+            .is_head_fallback());
+        assert_eq!(
+            router.resolve(&Method::GET, "/users/").unwrap().get_item(),
+            &2
+        );
+        assert_eq!(
+            router
+                .resolve(&Method::GET, "/users/42")
+                .unwrap()
+                .param_str("id"),
+            Option::Some("42")
+        );
+
+        router.remove("users").unwrap();
+        assert!(router.resolve(&Method::GET, "/users").is_err());
+        router.optimize_in_place();
+        assert!(router.resolve(&Method::GET, "/users").is_err());
+        assert_eq!(
+            router.resolve(&Method::POST, "/users").unwrap().get_item(),
+            &3
+        );
+    }
+
+    #[rstest(
+        path,
+        case("/files/a/b/c.txt"),
+        case("/files/c.txt"),
+        case("/files"),
+        case("/static/css/main.css")
+    )]
+    fn test_wildcard_link_round_trip(path: &str) {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("files", Method::GET, "/files/*path", 1).unwrap())
+            .unwrap()
+            .add(Route::create("static", Method::GET, "/static/*", 2).unwrap())
+            .unwrap();
+
+        let route_match = router.resolve(&Method::GET, path).unwrap();
+        let route_name = route_match.get_route_name().unwrap();
+
+        assert_eq!(
+            router
+                .link_path(route_name, route_match.get_params().clone())
+                .unwrap(),
+            path
+        );
+    }
+
+    #[rstest]
+    fn test_clear() {
+        let mut router = Router::builder(Url::parse("http://example.com/app/").unwrap())
+            .case_insensitive(true)
+            .fallback(0)
+            .build();
+        router
+            .add(Route::create("users", Method::GET, "/users", 1).unwrap())
+            .unwrap();
+        router.optimize_in_place();
+
+        router.clear();
+        assert_eq!(router.routes().count(), 0);
+        assert_eq!(router.stats().get_route_count(), 0);
+        assert_eq!(router.base().as_str(), "http://example.com/app/");
+        assert_eq!(
+            router.resolve(&Method::GET, "/users").unwrap().get_item(),
+            &0
+        );
+
+        router
+            .add(Route::create("users", Method::GET, "/users", 2).unwrap())
+            .unwrap();
+        assert_eq!(
+            router.resolve(&Method::GET, "/USERS").unwrap().get_item(),
+            &2
+        );
+    }
+
+    #[rstest]
+    fn test_shadows() {
+        let router = Router::from_routes(
+            Url::parse("http://example.com").unwrap(),
+            vec![
+                Route::create("users.me", Method::GET, "/users/me", 1).unwrap(),
+                Route::create("users.show", Method::GET, "/users/:id", 2).unwrap(),
+                Route::create("users.delete", Method::DELETE, "/users/:id", 3).unwrap(),
+                Route::create("users.numeric", Method::GET, "/users/:id(\\d+)/posts", 4).unwrap(),
+                Route::create("users.files", Method::GET, "/users/*path", 5).unwrap(),
+                Route::create_greedy("docs", Method::GET, "/docs", 6).unwrap(),
+            ],
+        );
+
+        assert_eq!(
+            router.shadows("/users/me"),
+            vec!["users.me", "users.delete", "users.show", "users.files"]
+        );
+        assert_eq!(
+            router.shadows("/users/42/posts"),
+            vec!["users.numeric", "users.files"]
+        );
+        assert_eq!(router.shadows("/users"), vec!["users.files"]);
+        assert_eq!(router.shadows("/docs/intro"), vec!["docs"]);
+        assert!(router.shadows("/other").is_empty());
+    }
+
+    #[rstest]
+    fn test_resolve_traced() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap();
+
+        let mut trace = Vec::new();
+        assert_eq!(
+            router
+                .resolve_traced(&Method::GET, "/users/42", &mut trace)
+                .unwrap()
+                .get_item(),
+            &1
+        );
+        assert_eq!(
+            trace
+                .iter()
+                .map(|step| (step.get_segment(), step.get_branch().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("users", TraceBranch::Static(String::from("users"))),
+                ("42", TraceBranch::Parameter(String::from("id"))),
+            ]
+        );
+
+        let mut trace = Vec::new();
+        assert!(router
+            .resolve_traced(&Method::GET, "/posts/42", &mut trace)
+            .is_err());
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].get_branch(), &TraceBranch::NotFound);
+        assert!(router
+            .add(Route::create("health", Method::GET, "/health", 2).unwrap())
+            .is_ok());
+        router.set_trailing_slash(TrailingSlash::Relaxed);
+        router.set_dot_segments(DotSegments::Normalize);
+        router.optimize_in_place();
+        let branches = |trace: &[TraceStep]| {
+            trace
+                .iter()
+                .map(|step| (String::from(step.get_segment()), step.get_branch().clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut trace = vec![TraceStep::new("stale", TraceBranch::NotFound)];
+        assert!(router
+            .resolve_traced(&Method::GET, "/posts/../users/42/", &mut trace)
+            .is_ok());
+        assert_eq!(
+            branches(&trace),
+            vec![
+                (
+                    String::from("users"),
+                    TraceBranch::Static(String::from("users"))
+                ),
+                (
+                    String::from("42"),
+                    TraceBranch::Parameter(String::from("id"))
+                ),
+            ]
+        );
+
+        let mut trace = Vec::new();
+        assert!(router
+            .resolve_traced(&Method::GET, "/health", &mut trace)
+            .is_ok());
+        assert_eq!(
+            branches(&trace),
+            vec![(
+                String::from("health"),
+                TraceBranch::Static(String::from("health"))
+            )]
+        );
+    }
+
+    #[rstest]
+    fn test_route_predicates() {
+        let content_type = |expected: &'static str| {
+            move |context: &PredicateContext<'_>| context.content_type() == Option::Some(expected)
+        };
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("upload", Method::POST, "/upload", 1).unwrap())
+            .unwrap()
+            .add(
+                Route::create("upload.json", Method::POST, "/upload", 2)
+                    .unwrap()
+                    .with_predicate(content_type("application/json")),
+            )
+            .unwrap()
+            .add(
+                Route::create("upload.form", Method::POST, "/upload", 3)
+                    .unwrap()
+                    .with_predicate(content_type("multipart/form-data")),
+            )
+            .unwrap();
+        assert!(router
+            .add(Route::create("upload.other", Method::POST, "/upload", 4).unwrap())
+            .is_err());
+
+        let resolve = |router: &Router<u64>, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_str(value).unwrap());
+            router
+                .resolve_with(&Method::POST, "/upload", &PredicateContext::new(&headers))
+                .map(|route_match| *route_match.get_item())
+        };
+
+        assert_eq!(
+            resolve(&router, "application/json; charset=utf-8").unwrap(),
+            2
+        );
+        assert_eq!(resolve(&router, "multipart/form-data").unwrap(), 3);
+        assert_eq!(resolve(&router, "text/plain").unwrap(), 1);
+        assert_eq!(
+            router.resolve(&Method::POST, "/upload").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(router.routes_under("/upload").len(), 3);
+
+        assert!(router.matches(&Method::POST, "/upload"));
+        assert_eq!(router.allowed_methods("/upload").unwrap(), [Method::POST]);
+
+        router.remove("upload").unwrap();
+        assert_eq!(resolve(&router, "application/json").unwrap(), 2);
+        assert!(resolve(&router, "text/plain").is_err());
+        assert!(router.resolve(&Method::POST, "/upload").is_err());
+        assert!(!router.matches(&Method::POST, "/upload"));
+        assert!(router.allowed_methods("/upload").is_err());
+        assert!(!router.path_exists("/upload"));
+
+        router
+            .add(Route::create("upload.page", Method::GET, "/upload", 5).unwrap())
+            .unwrap();
+        assert_eq!(router.allowed_methods("/upload").unwrap(), [Method::GET]);
+        router.remove("upload.page").unwrap();
+
+        router.remove("upload.json").unwrap();
+        router.remove("upload.form").unwrap();
+        assert!(!router.matches(&Method::POST, "/upload"));
+        router
+            .add(Route::create("upload", Method::POST, "/upload", 1).unwrap())
+            .unwrap();
+        assert_eq!(resolve(&router, "application/json").unwrap(), 1);
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[rstest]
+    fn test_routes_insertion_order() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        for (i, name) in ["users", "posts", "comments"].iter().enumerate() {
+            router
+                .add(Route::create(name, Method::GET, &format!("/{}", name), i).unwrap())
+                .unwrap();
+        }
+        router.remove("posts").unwrap();
+        router
+            .add(Route::create("tags", Method::GET, "/tags", 3).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router.routes().map(Route::get_name).collect::<Vec<&str>>(),
+            vec!["users", "comments", "tags"]
+        );
+    }
+
+    #[rstest]
+    fn test_reserve() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router.reserve(1000);
+        for i in 0..1000 {
+            router
+                .add(
+                    Route::create(&format!("r{}", i), Method::GET, &format!("/r/{}", i), i)
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(router.stats().get_route_count(), 1000);
+        assert_eq!(
+            router.resolve(&Method::GET, "/r/999").unwrap().get_item(),
+            &999
+        );
+    }
+
+    #[rstest(
+        dot_segments,
+        path,
+        expected,
+        case(DotSegments::Literal, "/admin", Some(2)),
+        case(DotSegments::Literal, "/users/../admin", None),
+        case(DotSegments::Normalize, "/users/../admin", Some(2)),
+        case(DotSegments::Normalize, "/users/%2E%2e/admin", Some(2)),
+        case(DotSegments::Normalize, "/../../admin", Some(2)),
+        case(DotSegments::Normalize, "/./users/5/.", Some(1)),
+        case(DotSegments::Normalize, "/users/.../admin", None),
+        case(DotSegments::Reject, "/admin", Some(2)),
+        case(DotSegments::Reject, "/users/../admin", None)
+    )]
+    fn test_dot_segments(dot_segments: DotSegments, path: &str, expected: Option<i32>) {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .set_dot_segments(dot_segments)
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap()
+            .add(Route::create("admin", Method::GET, "/admin", 2).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router
+                .resolve(&Method::GET, path)
+                .ok()
+                .map(|route_match| *route_match.get_item()),
+            expected
+        );
+        if dot_segments == DotSegments::Reject && path.contains("..") {
+            assert!(matches!(
+                router.resolve(&Method::GET, path),
+                Result::Err(RouterError::DotSegment { .. })
+            ));
+        }
+    }
+
+    #[rstest]
+    fn test_map() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap()
+            .add(Route::create("users", Method::GET, "/users", 2).unwrap())
+            .unwrap()
+            .set_fast_path(Method::GET, "/health", 3)
+            .set_fallback(4);
+
+        let router = router.map(|id| format!("handler{}", id));
+
+        let route_match = router.resolve(&Method::GET, "/users/5").unwrap();
+        assert_eq!(route_match.get_item(), "handler1");
+        assert_eq!(route_match.param_str("id"), Option::Some("5"));
+        assert_eq!(
+            router.resolve(&Method::GET, "/users").unwrap().get_item(),
+            "handler2"
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/health").unwrap().get_item(),
+            "handler3"
+        );
+        assert_eq!(
+            router.resolve(&Method::GET, "/posts").unwrap().get_item(),
+            "handler4"
+        );
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("7"));
+        assert_eq!(
+            router.link("user", params).unwrap().as_str(),
+            "http://example.com/users/7"
+        );
+    }
+
+    #[rstest]
+    fn test_error_status_hint() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .reject_encoded_separators(true)
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .unwrap();
+
+        let not_found = router.resolve(&Method::GET, "/posts").unwrap_err();
+        assert!(not_found.is_not_found());
+        assert!(!not_found.is_method_not_allowed());
+        assert_eq!(not_found.status_hint(), 404);
+
+        let not_allowed = router.resolve(&Method::POST, "/users/5").unwrap_err();
+        assert!(!not_allowed.is_not_found());
+        assert!(not_allowed.is_method_not_allowed());
+        assert_eq!(not_allowed.status_hint(), 405);
+
+        let rejected = router.resolve(&Method::GET, "/users/a%2Fb").unwrap_err();
+        assert!(rejected.is_rejected_path());
+        assert_eq!(rejected.status_hint(), 400);
+
+        let outside = router
+            .resolve_full(
+                &Method::GET,
+                &Url::parse("http://example.org/users/5").unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(outside.status_hint(), 404);
+
+        let duplicate = router
+            .add(Route::create("user", Method::GET, "/u/:id", 1).unwrap())
+            .unwrap_err();
+        assert!(!duplicate.is_not_found());
+        assert_eq!(duplicate.status_hint(), 500);
+        assert_eq!(
+            router.link("post", Map::new()).unwrap_err().status_hint(),
+            500
+        );
+    }
+
+    #[rstest]
+    fn test_route_aliases() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router
+            .add(
+                Route::create("user", Method::GET, "/users/:id", 1)
+                    .unwrap()
+                    .with_alias("/u/:id")
+                    .unwrap(),
+            )
+            .unwrap()
+            .add(
+                Route::create("about", Method::GET, "/about", 2)
+                    .unwrap()
+                    .with_alias("/info")
+                    .unwrap(),
+            )
+            .unwrap();
+        let mut router = router.optimize();
+
+        for path in &["/users/5", "/u/5"] {
+            let route_match = router.resolve(&Method::GET, path).unwrap();
+            assert_eq!(route_match.get_item(), &1);
+            assert_eq!(route_match.param_str("id"), Option::Some("5"));
+        }
+        assert_eq!(
+            router.resolve(&Method::GET, "/info").unwrap().get_item(),
+            &2
+        );
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("7"));
+        assert_eq!(
+            router.link("user", params).unwrap().as_str(),
+            "http://example.com/users/7"
+        );
+        assert_eq!(router.stats().get_route_count(), 2);
+        assert_eq!(router.routes_under("/").len(), 2);
+
+        assert_eq!(
+            router
+                .add(Route::create("short", Method::GET, "/u/:id", 3).unwrap())
+                .unwrap_err(),
+            RouterError::TreeError {
+                tree_error: TreeError::PathAlreadyRegistered {
+                    route: String::from("u/:id")
+                }
+            }
+        );
+        assert!(router
+            .add(
+                Route::create("post", Method::GET, "/posts/:id", 4)
+                    .unwrap()
+                    .with_alias("/u/:id")
+                    .unwrap()
+            )
+            .is_err());
+        assert!(router.resolve(&Method::GET, "/posts/5").is_err());
+        assert_eq!(
+            router
+                .add(
+                    Route::create("json", Method::POST, "/json", 5)
+                        .unwrap()
+                        .with_alias("/j")
+                        .unwrap()
+                        .with_predicate(|_| true)
+                )
+                .unwrap_err(),
+            RouterError::AliasWithPredicate {
+                route_name: String::from("json")
+            }
+        );
+
+        router.remove("user").unwrap();
+        assert!(router.resolve(&Method::GET, "/users/5").is_err());
+        assert!(router.resolve(&Method::GET, "/u/5").is_err());
+        assert!(router
+            .add(Route::create("short", Method::GET, "/u/:id", 3).unwrap())
+            .is_ok());
+
+        router.set_conflict_policy(ConflictPolicy::ReplaceExisting);
+        router
+            .add(Route::create("information", Method::GET, "/info", 6).unwrap())
+            .unwrap();
+        assert!(router.route("about").is_none());
+        assert!(router.resolve(&Method::GET, "/about").is_err());
+        assert_eq!(
+            router.resolve(&Method::GET, "/info").unwrap().get_item(),
+            &6
+        );
+
+        assert!(router
+            .add(
+                Route::create("twice", Method::GET, "/twice", 7)
+                    .unwrap()
+                    .with_alias("/twice")
+                    .unwrap()
+            )
+            .is_err());
+        assert!(router.resolve(&Method::GET, "/twice").is_err());
+    }
+
+    #[rstest]
+    fn test_route_aliases_rollback() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router.set_conflict_policy(ConflictPolicy::ReplaceExisting);
+        router
+            .add(
+                Route::create("a", Method::GET, "/x/:n", 1)
+                    .unwrap()
+                    .with_alias("/z/:n")
+                    .unwrap(),
+            )
+            .unwrap()
+            .add(
+                Route::create("c", Method::GET, "/y/:id", 3)
+                    .unwrap()
+                    .with_priority(1),
+            )
+            .unwrap();
+
+        assert!(router
+            .add(
+                Route::create("b", Method::GET, "/x/:n", 2)
+                    .unwrap()
+                    .with_alias(r"/y/:n(\d+)")
+                    .unwrap()
+            )
+            .is_err());
+        assert!(router.route("a").is_some());
+        assert!(router.route("b").is_none());
+        for path in &["/x/5", "/z/5"] {
+            assert_eq!(router.resolve(&Method::GET, path).unwrap().get_item(), &1);
+        }
+        assert_eq!(router.resolve(&Method::GET, "/y/5").unwrap().get_item(), &3);
+    }
+
+    #[rstest]
+    fn test_set_base() {
+        let mut router = Router::new(Url::parse("http://localhost:8080").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+        assert_eq!(router.base().as_str(), "http://localhost:8080/");
+
+        router.set_base(Url::parse("https://example.com").unwrap());
+        assert_eq!(router.base().as_str(), "https://example.com/");
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+        assert_eq!(
+            router.link("user", params).unwrap().to_string(),
+            "https://example.com/users/5"
+        );
+    }
+
+    #[rstest]
+    fn test_link_with_query() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+
+        assert_eq!(
+            router
+                .link_with_query("user", params.clone(), &[("page", "2"), ("sort", "name")])
+                .unwrap()
+                .to_string(),
+            "http://example.com/users/5?page=2&sort=name"
+        );
+        assert_eq!(
+            router
+                .link_with_query("user", params.clone(), &[("q", "a b&c=d")])
+                .unwrap()
+                .to_string(),
+            "http://example.com/users/5?q=a+b%26c%3Dd"
+        );
+        assert_eq!(
+            router
+                .link_with_query("user", params, &[])
+                .unwrap()
+                .to_string(),
+            "http://example.com/users/5"
+        );
+        assert!(router
+            .link_with_query("post", Map::new(), &[("page", "2")])
+            .is_err());
+    }
+
+    #[rstest]
+    fn test_mount() {
+        let base = Url::parse("http://example.com").unwrap();
+        let users = || {
+            let mut users = Router::new(Url::parse("http://users.example.com").unwrap());
+            assert!(users
+                .add(Route::create("index", Method::GET, "/", 1).unwrap())
+                .is_ok());
+            assert!(users
+                .add(Route::create("show", Method::GET, "/:id", 2).unwrap())
+                .is_ok());
+            users
+        };
+
+        let mut router = Router::new(base);
+        assert!(router
+            .add(Route::create("index", Method::GET, "/", 0).unwrap())
+            .is_ok());
+        assert!(router
+            .mount_with_namespace("/users", "users", users())
+            .is_ok());
+
+        let m = &Method::GET;
+        assert_eq!(router.resolve(m, "/").unwrap().get_item(), &0);
+        assert_eq!(router.resolve(m, "/users").unwrap().get_item(), &1);
+
+        let route_match = router.resolve(m, "/users/5").unwrap();
+        assert_eq!(route_match.get_item(), &2);
+        assert_eq!(route_match.get_route_name(), Option::Some("users.show"));
+
+        let mut params = Map::new();
+        params.insert(String::from("id"), String::from("5"));
+        assert_eq!(
+            router.link("users.show", params).unwrap().to_string(),
+            "http://example.com/users/5"
+        );
+
+        assert_eq!(
+            router.mount("/people", users()).unwrap_err(),
+            RouterError::RouteAlreadyExists {
+                route_name: String::from("index")
+            }
+        );
+        assert!(router
+            .mount_with_namespace("/users", "people", users())
+            .is_err());
+        assert!(router.route("people.index").is_none());
+        assert!(router.route("people.show").is_none());
+        assert_eq!(router.routes().count(), 3);
+    }
+
+    #[rstest]
+    fn test_mount_rollback_replaced() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router.set_conflict_policy(ConflictPolicy::ReplaceExisting);
+        router
+            .add(Route::create("x", Method::GET, "/m/a", 1).unwrap())
+            .unwrap()
+            .add(Route::create("m.dup", Method::GET, "/dup", 2).unwrap())
+            .unwrap();
+
+        let mut other = Router::new(Url::parse("http://example.com").unwrap());
+        other
+            .add(Route::create("a", Method::GET, "/a", 3).unwrap())
+            .unwrap()
+            .add(Route::create("dup", Method::GET, "/other", 4).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router.mount_with_namespace("/m", "m", other).unwrap_err(),
+            RouterError::RouteAlreadyExists {
+                route_name: String::from("m.dup")
+            }
+        );
+        assert!(router.route("m.a").is_none());
+        assert_eq!(router.resolve(&Method::GET, "/m/a").unwrap().get_item(), &1);
+        assert_eq!(router.route("x").unwrap().get_item(), &1);
+        assert_eq!(router.routes().count(), 2);
+    }
+
+    #[rstest]
+    fn test_scope() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .scope("/api/v1", |s| {
+                s.add(Route::create("users", Method::GET, "/users", 1).unwrap())
+                    .add(Route::create("user", Method::GET, "/users/:id", 2).unwrap());
+            })
+            .is_ok());
+
+        let m = &Method::GET;
+        assert_eq!(router.resolve(m, "/api/v1/users").unwrap().get_item(), &1);
+        assert_eq!(router.resolve(m, "/api/v1/users/5").unwrap().get_item(), &2);
+        assert!(router.resolve(m, "/users").is_err());
+
+        assert_eq!(
+            router
+                .scope("/api/v2", |s| {
+                    s.add(Route::create("users.v2", Method::GET, "/users", 3).unwrap())
+                        .add(Route::create("user", Method::GET, "/users/:id", 4).unwrap())
+                        .add(Route::create("posts.v2", Method::GET, "/posts", 5).unwrap());
+                })
+                .unwrap_err(),
+            RouterError::RouteAlreadyExists {
+                route_name: String::from("user")
+            }
+        );
+        assert!(router.resolve(m, "/api/v2/users").is_err());
+        assert!(router.route("posts.v2").is_none());
+        assert_eq!(router.routes().count(), 2);
+
+        assert_eq!(
+            router.scope("/files/*path", |_| {}).unwrap_err(),
+            RouterError::PathError {
+                path_error: PathError::WildcardItemMustBeLast
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_scope_rollback_replaced() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+        router.set_conflict_policy(ConflictPolicy::ReplaceExisting);
+        router
+            .add(Route::create("x", Method::GET, "/api/a", 1).unwrap())
+            .unwrap()
+            .add(Route::create("dup", Method::GET, "/dup", 2).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            router
+                .scope("/api", |s| {
+                    s.add(Route::create("a", Method::GET, "/a", 3).unwrap())
+                        .add(Route::create("dup", Method::GET, "/other", 4).unwrap());
+                })
+                .unwrap_err(),
+            RouterError::RouteAlreadyExists {
+                route_name: String::from("dup")
+            }
+        );
+        assert!(router.route("a").is_none());
+        assert_eq!(
+            router.resolve(&Method::GET, "/api/a").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(router.routes().count(), 2);
+    }
+
+    #[rstest]
+    fn test_remove() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 1).unwrap())
+            .is_ok());
+
+        let m = &Method::GET;
+
+        assert_eq!(router.resolve(m, "/users/5").unwrap().get_item(), &1);
+
+        let route = router.remove("user").unwrap();
+        assert_eq!(route.get_name(), "user");
+        assert!(router.resolve(m, "/users/5").is_err());
+        assert!(router.link("user", Map::new()).is_err());
+        assert_eq!(
+            router.remove("user").unwrap_err(),
+            RouterError::RouteNotFound {
+                route_name: String::from("user")
+            }
+        );
+
+        assert!(router.add(route).is_ok());
+        assert_eq!(router.resolve(m, "/users/5").unwrap().get_item(), &1);
+    }
+
+    #[rstest]
+    fn test_trailing_slash() {
+        let mut router = Router::with_index(
+            Url::parse("http://example.com").unwrap(),
+            StaticIndex::default(),
+        );
+
+        assert!(router
+            .add(Route::create("users", Method::GET, "/users", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("root", Method::GET, "/", 2).unwrap())
+            .is_ok());
+
+        let m = &Method::GET;
+
+        assert!(router.resolve(m, "/users/").is_err());
+
+        router.set_trailing_slash(TrailingSlash::Relaxed);
+
+        assert_eq!(router.resolve(m, "/users/").unwrap().get_item(), &1);
+        assert_eq!(router.resolve(m, "/users").unwrap().get_item(), &1);
+        assert_eq!(router.resolve(m, "/").unwrap().get_item(), &2);
+    }
+
+    #[rstest]
+    fn test_trailing_slash_wildcard() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("files", Method::GET, "/files/*path", 1).unwrap())
+            .is_ok());
+        router.set_trailing_slash(TrailingSlash::Relaxed);
+
+        let m = &Method::GET;
+
+        assert_eq!(
+            router
+                .resolve(m, "/files/")
+                .unwrap()
+                .get_params()
+                .get("path")
+                .unwrap(),
+            ""
+        );
+        assert_eq!(router.resolve(m, "/files").unwrap().get_item(), &1);
+        assert_eq!(router.resolve(m, "/files/a/").unwrap().get_item(), &1);
+    }
+
+    #[rstest]
+    fn test_decode_params() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("file", Method::GET, "/files/:name", 1).unwrap())
+            .is_ok());
+
+        let m = &Method::GET;
+
+        assert_eq!(
+            router
+                .resolve(m, "/files/my%20file.txt")
+                .unwrap()
+                .get_params()
+                .get("name")
+                .unwrap(),
+            "my%20file.txt"
+        );
+
+        router.decode_params(true);
+
+        assert_eq!(
+            router
+                .resolve(m, "/files/my%20file.txt")
+                .unwrap()
+                .get_params()
+                .get("name")
+                .unwrap(),
+            "my file.txt"
+        );
+    }
+
+    #[rstest]
+    fn test_routes() {
+        let mut router = Router::new(Url::parse("http://example.com").unwrap());
+
+        assert!(router
+            .add(Route::create("root", Method::GET, "/", 1).unwrap())
+            .is_ok());
+        assert!(router
+            .add(Route::create("user", Method::GET, "/users/:id", 2).unwrap())
+            .is_ok());
+
+        let mut names = router
+            .routes()
+            .map(|route| route.get_name())
+            .collect::<Vec<&str>>();
+        names.sort_unstable();
+        assert_eq!(names, vec!["root", "user"]);
+
+        assert_eq!(router.route("user").unwrap().get_item(), &2);
+        assert!(router.route("missing").is_none());
     }
 }
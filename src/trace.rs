@@ -0,0 +1,43 @@
+/// A step of a traced resolution, see
+/// [`Router::resolve_traced`](struct.Router.html#method.resolve_traced).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    segment: String,
+    branch: TraceBranch,
+}
+
+impl TraceStep {
+    pub(crate) fn new(segment: &str, branch: TraceBranch) -> Self {
+        TraceStep {
+            segment: String::from(segment),
+            branch,
+        }
+    }
+
+    /// Return the segment of the path the step was taken for.
+    ///
+    /// For a wildcard, it is the rest of the path the wildcard captured.
+    pub fn get_segment(&self) -> &str {
+        &self.segment
+    }
+
+    /// Return the branch taken for the segment.
+    pub fn get_branch(&self) -> &TraceBranch {
+        &self.branch
+    }
+}
+
+/// The decision taken for a segment of a traced resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceBranch {
+    /// the segment matched a static segment, with its registered casing
+    Static(String),
+    /// the segment was captured by a parameter
+    Parameter(String),
+    /// the rest of the path was captured by a wildcard
+    Wildcard(String),
+    /// the rest of the path was left unconsumed by a greedy route
+    Greedy,
+    /// no static segment, parameter or wildcard accepted the segment
+    NotFound,
+}
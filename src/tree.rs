@@ -1,10 +1,19 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, cmp::Reverse, fmt::Debug};
 
+use crate::map::{Map, SmallMap};
+use crate::path::Constraint;
 use crate::path::Item;
 use crate::path::Path;
+use crate::path::ANY_METHOD;
+use crate::path::ESCAPE;
+use crate::ConflictPolicy;
+use crate::LookupOptions;
 use crate::RouteMatch;
-use crate::{map::Map, RouteParameter};
+use crate::RouteParameter;
+use crate::RouterStats;
+use crate::{TraceBranch, TraceStep};
 use http::Method;
+use percent_encoding::percent_decode_str;
 use thiserror::Error;
 
 pub const PATH_SEPARATOR: &str = "/";
@@ -26,71 +35,193 @@ where
     }
 
     pub fn add(&mut self, path: Path, item: T) -> Result<(), TreeError> {
+        self.add_with(path, item, ConflictPolicy::Reject)
+            .map(|_| ())
+    }
+
+    /// Adds a path, handling conflicts with the registered paths by the given policy.
+    ///
+    /// The items that are not registered anymore are returned: the replaced ones, or the given
+    /// one when it was ignored.
+    pub fn add_with(
+        &mut self,
+        path: Path,
+        item: T,
+        policy: ConflictPolicy,
+    ) -> Result<Vec<T>, TreeError> {
         let mut current = &mut self.root;
+        let mut displaced = Vec::new();
 
-        for item in path.get_items() {
-            current = current.ensure(item).map_err(|err| match err {
-                NodeError::PathAlreadyRegistered => TreeError::PathAlreadyRegistered {
-                    route: path.render_original(),
-                },
-            })?;
+        let items = path.get_items();
+        for (index, (segment, constraint)) in items.iter().zip(path.get_constraints()).enumerate() {
+            current = match current.ensure(
+                segment,
+                constraint,
+                path.get_priority(),
+                index + 1 == items.len(),
+                policy,
+                &mut displaced,
+            ) {
+                Result::Ok(Option::Some(node)) => node,
+                Result::Ok(Option::None) => return Result::Ok(vec![item]),
+                Result::Err(NodeError::ConflictingDynamicSegment { existing, incoming }) => {
+                    return Result::Err(TreeError::ConflictingDynamicSegment { existing, incoming })
+                }
+                Result::Err(NodeError::UnreachableRoute { shadowed_by }) => {
+                    return Result::Err(TreeError::UnreachableRoute {
+                        route: path.render_original(),
+                        shadowed_by,
+                    })
+                }
+                Result::Err(NodeError::ShadowingRoute { shadowed }) => {
+                    return Result::Err(TreeError::ShadowingRoute {
+                        route: path.render_original(),
+                        shadowed,
+                    })
+                }
+            };
         }
 
         if current.has(path.get_method()) {
-            return Err(TreeError::PathAlreadyRegistered {
-                route: path.render_original(),
-            });
+            match policy {
+                ConflictPolicy::Reject => {
+                    return Result::Err(TreeError::PathAlreadyRegistered {
+                        route: path.render_original(),
+                    })
+                }
+                ConflictPolicy::KeepExisting => return Result::Ok(vec![item]),
+                ConflictPolicy::ReplaceExisting => {
+                    current.greedy.remove(path.get_method());
+                    displaced.extend(current.item.remove(path.get_method()));
+                }
+            }
         }
 
-        current.set(path.get_method().clone(), item);
+        current.set(
+            path.get_method().clone(),
+            item,
+            path.is_greedy(),
+            path.get_priority(),
+        );
+
+        Result::Ok(displaced)
+    }
 
-        Result::Ok(())
+    pub fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<'_, T>, TreeError> {
+        self.lookup_with(method, path, &LookupOptions::default())
+            .map(|(route_match, _)| route_match)
     }
 
-    pub fn lookup(&self, method: &Method, path: &str) -> Result<RouteMatch<T>, TreeError> {
-        let pieces: Vec<&str> = path
-            .split(PATH_SEPARATOR)
-            .filter(|item| !item.is_empty())
-            .collect();
-        let mut current_node = &self.root;
-        let mut params = RouteParameter::new();
+    /// Looks up a path with the given options.
+    ///
+    /// When the path matched case insensitively and its casing differs from the registered one,
+    /// the path with the registered casing is returned as well.
+    pub fn lookup_with(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        self.lookup_walk(method, &mut Walk::new(path, options))
+    }
 
-        for i in 0..pieces.len() {
-            let piece = match pieces.get(i) {
-                Option::None => unreachable!(),
-                Option::Some(p) => p,
-            };
-            match current_node.get_child(piece) {
-                Option::None => {
-                    return Result::Err(TreeError::PathNotFound {
-                        path: String::from(path),
-                    })
-                }
-                Option::Some(res) => {
-                    current_node = res.item;
-                    let param_name = String::from(&res.name[1..]);
-                    match res.loop_behavior {
-                        LoopBehavior::Ignore => {}
-                        LoopBehavior::Collect => {
-                            params.insert(param_name, String::from(*piece));
-                        }
-                        LoopBehavior::Finish => {
-                            params.insert(
-                                param_name,
-                                String::from(&pieces[i..].join(PATH_SEPARATOR)),
-                            );
-                            break;
-                        }
-                    }
-                }
+    /// Looks up a path like [`lookup_with`](#method.lookup_with), recording the branches taken
+    /// for its segments into `trace`.
+    ///
+    /// On a match, the steps lead to the matched route, without the branches the walk
+    /// backtracked from. Otherwise they lead to the first dead end of the walk, ending with the
+    /// segment no child accepted, if any.
+    pub fn lookup_traced(
+        &self,
+        method: &Method,
+        path: &str,
+        options: &LookupOptions,
+        trace: &mut Vec<TraceStep>,
+    ) -> Result<(RouteMatch<'_, T>, Option<String>), TreeError> {
+        let mut walk = Walk::new(path, options);
+        walk.steps = Option::Some(Vec::new());
+        let found = self.lookup_walk(method, &mut walk);
+        trace.extend(
+            walk.matched_steps
+                .or(walk.dead_end_steps)
+                .unwrap_or_default(),
+        );
+
+        found
+    }
+
+    fn lookup_walk<'t>(
+        &'t self,
+        method: &Method,
+        walk: &mut Walk<'t, '_>,
+    ) -> Result<(RouteMatch<'t, T>, Option<String>), TreeError> {
+        let path = walk.path;
+        let options = walk.options;
+        let mut found = Option::None;
+        walk.run(&self.root, &mut |walk, terminal| {
+            let item = terminal.get_item(method)?;
+            let priority = terminal.get_priority(method);
+            if !walk.is_preferred(priority) {
+                return Option::None;
             }
+            found = Option::Some(walk.create_match(item, terminal.kind));
+
+            Option::Some(priority)
+        });
+        if let Option::Some(found) = found {
+            return Result::Ok(found);
         }
 
-        current_node
-            .get_item(method)
-            .map(|item| RouteMatch::create(item, params))
-            .ok_or_else(|| TreeError::MethodNotFound {
+        match self.allowed_methods(path, &options) {
+            Result::Ok(allowed) => Result::Err(TreeError::MethodNotFound {
                 method: method.clone(),
+                allowed,
+            }),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// Checks whether a path resolves like [`lookup_with`](#method.lookup_with) does, without
+    /// capturing the parameters.
+    pub fn contains(&self, method: &Method, path: &str, options: &LookupOptions) -> bool {
+        // Any route will do, so the walk stops at the first one instead of looking for the one
+        // of the highest priority.
+        Walk::new(path, options).run(&self.root, &mut |_, terminal| {
+            terminal.get_item(method).map(|_| i32::MAX)
+        })
+    }
+
+    /// Returns the methods the given path resolves with.
+    ///
+    /// The tree is walked the same way as in [`lookup_with`](#method.lookup_with), collecting
+    /// the methods of every route the path matches, including the wildcards and greedy routes
+    /// taking the rest of it.
+    pub fn allowed_methods(
+        &self,
+        path: &str,
+        options: &LookupOptions,
+    ) -> Result<Vec<Method>, TreeError> {
+        let mut allowed = Vec::new();
+        Walk::new(path, options).run(&self.root, &mut |_, terminal| {
+            allowed.extend(terminal.get_methods());
+            Option::None
+        });
+        if allowed.is_empty() {
+            return Result::Err(TreeError::PathNotFound {
+                path: String::from(path),
+            });
+        }
+        allowed.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        allowed.dedup();
+
+        Result::Ok(allowed)
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Result<T, TreeError> {
+        self.root
+            .remove(path.get_items(), path.get_constraints(), path.get_method())
+            .ok_or_else(|| TreeError::PathNotFound {
+                path: path.render_original(),
             })
     }
 
@@ -99,259 +230,1324 @@ where
 
         self
     }
+
+    /// Visits every node with items depth first, with its pattern and its methods sorted by
+    /// their name.
+    ///
+    /// The pattern is built from the static segments and the parameter and wildcard segments as
+    /// they were registered, e.g. `/users/:id`. Static children are visited in the order of
+    /// their names, dynamic children in the order of their priority.
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, &[Method]),
+    {
+        self.root.walk(&mut String::new(), &mut f);
+    }
+
+    /// Returns the items of every path starting with the static segments of the prefix, in no
+    /// particular order.
+    ///
+    /// The segments of the prefix are matched exactly, parameters are not matched.
+    pub fn items_under(&self, prefix: &str) -> Vec<&T> {
+        let mut current = &self.root;
+        for (_, piece) in split_pieces(prefix) {
+            match current.static_children.get(piece) {
+                Option::Some(child) => current = child,
+                Option::None => return Vec::new(),
+            }
+        }
+
+        let mut items = Vec::new();
+        current.collect_items(&mut items);
+
+        items
+    }
+
+    /// Returns the items of every path matching the concrete path, in the order the lookup
+    /// would prefer them, and the items of a path by their method names.
+    ///
+    /// The segments are matched as they are, without the lookup options.
+    pub fn matching_items(&self, path: &str) -> Vec<&T> {
+        let pieces: Vec<&str> = split_pieces(path).map(|(_, piece)| piece).collect();
+        let mut items = Vec::new();
+        self.root.collect_matches(&pieces, &mut items);
+
+        items
+    }
+
+    /// Counts the nodes and the routes of the tree.
+    pub fn stats(&self) -> RouterStats {
+        let mut stats = RouterStats::default();
+        self.root.collect_stats(0, false, &mut stats);
+
+        stats
+    }
 }
 
-#[derive(Debug, Clone)]
-struct Node<T: Clone + Debug> {
-    static_children: Map<String, Box<Node<T>>>,
-    dynamic_child: Option<DynamicChild<T>>,
-    item: Map<Method, T>,
+/// A depth first walk of the tree along the pieces of a path.
+///
+/// Every child accepting a piece is tried in order: the static child first, then the dynamic
+/// children in their order, and a greedy node takes the rest of the path when none of its
+/// children lead to a match. So a branch that dead-ends, e.g. a constrained parameter whose
+/// subtree has no route for the rest of the path, is unwound and its next sibling is tried.
+///
+/// The places where the walk can end are passed to a visitor in the order they are preferred,
+/// and the visitor accepts one by returning the priority of its route. A later dynamic sibling
+/// is still tried when the routes under it may be of a higher priority, otherwise the walk
+/// stops at the first accepted place.
+struct Walk<'t, 'p> {
+    path: &'p str,
+    options: LookupOptions,
+    case_insensitive: bool,
+    decode_params: bool,
+    params: Vec<(&'t str, &'p str)>,
+    // The registered names of the static segments matched so far, or `None` for the
+    // parameters, only kept when matching case insensitively.
+    canonical: Vec<Option<&'t str>>,
+    consumed: usize,
+    // The priority of the route accepted so far.
+    accepted: Option<i32>,
+    // The branches taken for the pieces so far, only kept when tracing, along with the ones
+    // leading to the route accepted so far and to the first dead end.
+    steps: Option<Vec<TraceStep>>,
+    matched_steps: Option<Vec<TraceStep>>,
+    dead_end_steps: Option<Vec<TraceStep>>,
 }
 
-impl<T> Node<T>
-where
-    T: Clone + Debug,
-{
-    pub fn new() -> Self {
-        Node {
-            static_children: Map::new(),
-            dynamic_child: Option::None,
-            item: Map::new(),
+impl<'t, 'p> Walk<'t, 'p> {
+    fn new(path: &'p str, options: &LookupOptions) -> Self {
+        Walk {
+            path,
+            options: *options,
+            case_insensitive: options.is_case_insensitive(),
+            decode_params: options.is_decode_params(),
+            params: Vec::new(),
+            canonical: Vec::new(),
+            consumed: 0,
+            accepted: Option::None,
+            steps: Option::None,
+            matched_steps: Option::None,
+            dead_end_steps: Option::None,
         }
     }
 
-    pub fn ensure(&mut self, item: &Item) -> Result<&mut Node<T>, NodeError> {
-        match item {
-            Item::Static(ref name) => {
-                if !self.static_children.contains_key(name) {
-                    self.static_children
-                        .insert(String::from(name), Box::new(Node::new()));
-                }
+    fn push_step<S>(&mut self, step: S)
+    where
+        S: FnOnce() -> TraceStep,
+    {
+        if let Option::Some(steps) = self.steps.as_mut() {
+            steps.push(step());
+        }
+    }
 
-                Result::Ok(self.static_children.get_mut(name).unwrap())
-            }
-            Item::Parameter(ref name) => {
-                if self.dynamic_child.is_none() {
-                    self.dynamic_child = Option::Some(DynamicChild::create(
-                        String::from(name),
-                        DynamicChildType::Parameter(Box::new(Node::new())),
-                    ));
-                } else {
-                    return Result::Err(NodeError::PathAlreadyRegistered);
+    fn pop_step(&mut self) {
+        if let Option::Some(steps) = self.steps.as_mut() {
+            steps.pop();
+        }
+    }
+
+    /// Records the steps so far as the first dead end of the walk, with the piece no child
+    /// accepted.
+    fn dead_end(&mut self, piece: Option<&str>) {
+        if let (Option::Some(steps), Option::None) = (&self.steps, &self.dead_end_steps) {
+            let mut steps = steps.clone();
+            steps.extend(piece.map(|piece| TraceStep::new(piece, TraceBranch::NotFound)));
+            self.dead_end_steps = Option::Some(steps);
+        }
+    }
+
+    /// Checks whether a route of the priority would be preferred to the one accepted so far.
+    fn is_preferred(&self, priority: i32) -> bool {
+        self.accepted.is_none_or(|accepted| priority > accepted)
+    }
+
+    fn offer<T, F>(&mut self, visit: &mut F, terminal: Terminal<'t, T>) -> bool
+    where
+        T: Clone + Debug,
+        F: FnMut(&Self, Terminal<'t, T>) -> Option<i32>,
+    {
+        match visit(self, terminal) {
+            Option::Some(priority) => {
+                self.accepted = Option::Some(priority);
+                if let Option::Some(steps) = &self.steps {
+                    let mut steps = steps.clone();
+                    steps.extend(self.terminal_step(terminal.kind));
+                    self.matched_steps = Option::Some(steps);
                 }
+                true
+            }
+            Option::None => false,
+        }
+    }
 
-                Result::Ok(match &mut self.dynamic_child {
-                    Option::Some(ref mut child) => child.get_mut_child_type().get_mut(),
-                    _ => unreachable!(),
-                })
+    fn run<T, F>(&mut self, root: &'t Node<T>, visit: &mut F) -> bool
+    where
+        T: Clone + Debug,
+        F: FnMut(&Self, Terminal<'t, T>) -> Option<i32>,
+    {
+        self.descend(root, split_pieces(self.path), visit)
+    }
+
+    fn descend<T, I, F>(&mut self, node: &'t Node<T>, mut pieces: I, visit: &mut F) -> bool
+    where
+        T: Clone + Debug,
+        I: Iterator<Item = (usize, &'p str)> + Clone,
+        F: FnMut(&Self, Terminal<'t, T>) -> Option<i32>,
+    {
+        let (offset, raw) = match pieces.next() {
+            Option::None => return self.finish(node, visit),
+            Option::Some(piece) => piece,
+        };
+        let piece = if self.decode_params {
+            percent_decode_str(raw).decode_utf8_lossy()
+        } else {
+            Cow::Borrowed(raw)
+        };
+
+        let mut accepted_piece = false;
+        if let Option::Some((name, child)) = node.get_static_child(&piece, self.case_insensitive) {
+            accepted_piece = true;
+            self.push_step(|| TraceStep::new(&piece, TraceBranch::Static(String::from(name))));
+            let found = self.enter(Option::Some(name), child, pieces.clone(), visit);
+            self.pop_step();
+            if found {
+                return true;
             }
-            Item::Wildcard(ref name) => {
-                if self.dynamic_child.is_none() {
-                    self.dynamic_child = Option::Some(DynamicChild::create(
-                        String::from(name),
-                        DynamicChildType::Wildcard(Box::new(Node::new())),
-                    ));
-                } else {
-                    return Result::Err(NodeError::PathAlreadyRegistered);
+        }
+        let mut found = false;
+        for child in node
+            .dynamic_children
+            .iter()
+            .filter(|child| child.accepts(&piece))
+        {
+            accepted_piece = true;
+            if !self.is_preferred(child.get_route_priority()) {
+                continue;
+            }
+            found |= match child.get_child_type() {
+                DynamicChildType::Wildcard(wildcard) => self.offer(
+                    visit,
+                    Terminal::new(
+                        wildcard,
+                        TerminalKind::Wildcard {
+                            name: child.get_name(),
+                            offset: Option::Some(offset),
+                        },
+                    ),
+                ),
+                DynamicChildType::Parameter(next) | DynamicChildType::OptionalParameter(next) => {
+                    self.params.push((child.get_name(), raw));
+                    self.push_step(|| {
+                        TraceStep::new(&piece, TraceBranch::Parameter(child.get_name().into()))
+                    });
+                    let found = self.enter(Option::None, next, pieces.clone(), visit);
+                    self.pop_step();
+                    self.params.pop();
+                    found
                 }
+            };
+        }
 
-                Result::Ok(match &mut self.dynamic_child {
-                    Option::Some(ref mut child) => child.get_mut_child_type().get_mut(),
-                    _ => unreachable!(),
-                })
-            }
+        if found
+            || (node.has_greedy()
+                && self.offer(visit, Terminal::new(node, TerminalKind::Greedy { offset })))
+        {
+            return true;
+        }
+        if !accepted_piece {
+            self.dead_end(Option::Some(&piece));
         }
-    }
 
-    pub fn set(&mut self, method: Method, item: T) {
-        self.item.insert(method, item);
+        false
     }
 
-    pub fn has(&self, method: &Method) -> bool {
-        self.item.contains_key(method)
+    fn enter<T, I, F>(
+        &mut self,
+        registered: Option<&'t str>,
+        node: &'t Node<T>,
+        pieces: I,
+        visit: &mut F,
+    ) -> bool
+    where
+        T: Clone + Debug,
+        I: Iterator<Item = (usize, &'p str)> + Clone,
+        F: FnMut(&Self, Terminal<'t, T>) -> Option<i32>,
+    {
+        self.consumed += 1;
+        if self.case_insensitive {
+            self.canonical.push(registered);
+        }
+        let found = self.descend(node, pieces, visit);
+        self.consumed -= 1;
+        if self.case_insensitive {
+            self.canonical.pop();
+        }
+
+        found
     }
 
-    pub fn get_child(&self, name: &str) -> Option<LookupResult<T>> {
-        self.static_children
-            .get(name)
-            .map(|boxed_child| {
-                LookupResult::create(
-                    boxed_child.as_ref(),
-                    String::from(name),
-                    LoopBehavior::Ignore,
+    /// Visits the places a walk that consumed every piece can end at.
+    ///
+    /// On a trailing slash an optional parameter child is captured as an empty value before
+    /// the node itself is tried. A wildcard child also captures zero segments, e.g. both
+    /// `/files` and `/files/` match `/files/*path` with an empty `path`.
+    fn finish<T, F>(&mut self, node: &'t Node<T>, visit: &mut F) -> bool
+    where
+        T: Clone + Debug,
+        F: FnMut(&Self, Terminal<'t, T>) -> Option<i32>,
+    {
+        let trailing_slash = self.consumed > 0 && self.path.ends_with(PATH_SEPARATOR);
+        let optional = node
+            .get_optional_child(trailing_slash)
+            .map(|(optional, name)| {
+                Terminal::new(
+                    optional,
+                    TerminalKind::Optional {
+                        name,
+                        empty: trailing_slash,
+                    },
                 )
-            })
-            .or_else(|| {
-                self.dynamic_child.as_ref().and_then(|child| {
-                    let name = String::from(child.get_name());
-                    let child_type = child.get_child_type();
-
-                    child_type
-                        .get_parameter()
-                        .map(|item| LookupResult::create(item, name.clone(), LoopBehavior::Collect))
-                        .or_else(|| {
-                            child_type.get_wildcard().map(|item| {
-                                LookupResult::create(item, name.clone(), LoopBehavior::Finish)
-                            })
-                        })
-                })
-            })
+            });
+
+        if trailing_slash && optional.is_some_and(|optional| self.offer(visit, optional)) {
+            return true;
+        }
+        if self.offer(visit, Terminal::new(node, TerminalKind::Exact)) {
+            return true;
+        }
+        if !trailing_slash && optional.is_some_and(|optional| self.offer(visit, optional)) {
+            return true;
+        }
+
+        let found = node.get_wildcard_child("").is_some_and(|(wildcard, name)| {
+            self.offer(
+                visit,
+                Terminal::new(
+                    wildcard,
+                    TerminalKind::Wildcard {
+                        name,
+                        offset: Option::None,
+                    },
+                ),
+            )
+        });
+        if !found {
+            self.dead_end(Option::None);
+        }
+
+        found
     }
 
-    pub fn get_item(&self, method: &Method) -> Option<&T> {
-        self.item.get(method)
+    /// Returns the step of a place the walk ended at, unless it is the node of the last piece.
+    fn terminal_step(&self, kind: TerminalKind<'t>) -> Option<TraceStep> {
+        match kind {
+            TerminalKind::Exact | TerminalKind::Optional { empty: false, .. } => Option::None,
+            TerminalKind::Optional { name, empty: true } => Option::Some(TraceStep::new(
+                "",
+                TraceBranch::Parameter(String::from(name)),
+            )),
+            TerminalKind::Wildcard { name, offset } => Option::Some(TraceStep::new(
+                &offset.map_or_else(String::new, |offset| {
+                    rest_of(self.path, offset, self.decode_params)
+                }),
+                TraceBranch::Wildcard(String::from(name)),
+            )),
+            TerminalKind::Greedy { offset } => Option::Some(TraceStep::new(
+                &rest_of(self.path, offset, false),
+                TraceBranch::Greedy,
+            )),
+        }
     }
 
-    pub fn optimize(&mut self) -> &Self {
-        self.static_children.optimize();
+    /// Creates the match of an item found at the end of the walk, with the path in the
+    /// registered casing when it differs.
+    fn create_match<T>(
+        &self,
+        item: &'t T,
+        kind: TerminalKind<'t>,
+    ) -> (RouteMatch<'t, T>, Option<String>) {
+        let mut params = RouteParameter::new();
+        for (name, raw) in self.params.iter() {
+            let value = if self.decode_params {
+                percent_decode_str(raw).decode_utf8_lossy().into_owned()
+            } else {
+                String::from(*raw)
+            };
+            params.insert(String::from(*name), value);
+        }
 
-        for (_, v) in self.static_children.iter_mut() {
-            v.optimize();
+        let route_match = match kind {
+            TerminalKind::Exact => RouteMatch::create(item, params),
+            TerminalKind::Optional { name, empty } => {
+                if empty {
+                    params.insert(String::from(name), String::new());
+                }
+                RouteMatch::create(item, params)
+            }
+            TerminalKind::Wildcard { name, offset } => {
+                let (rest, ends) = match offset {
+                    Option::Some(offset) => wildcard_rest_of(self.path, offset, self.decode_params),
+                    Option::None => (String::new(), Vec::new()),
+                };
+                params.insert(String::from(name), rest);
+                RouteMatch::create(item, params).with_wildcard_ends(name, ends)
+            }
+            TerminalKind::Greedy { offset } => {
+                RouteMatch::create(item, params).with_remainder(Option::Some(format!(
+                    "{}{}",
+                    PATH_SEPARATOR,
+                    rest_of(self.path, offset, false)
+                )))
+            }
+        };
+
+        (route_match, self.canonical_path(kind))
+    }
+
+    /// Renders the path with the registered casing of its static segments, when it differs.
+    fn canonical_path(&self, kind: TerminalKind<'t>) -> Option<String> {
+        if !self.case_insensitive {
+            return Option::None;
         }
 
-        if let Option::Some(ref mut dc) = self.dynamic_child {
-            dc.get_mut_child_type().get_mut().optimize();
+        let mut matched: Vec<&str> = split_pieces(self.path)
+            .zip(self.canonical.iter())
+            .map(|((_, raw), registered)| registered.unwrap_or(raw))
+            .collect();
+        match kind {
+            TerminalKind::Wildcard {
+                offset: Option::Some(offset),
+                ..
+            }
+            | TerminalKind::Greedy { offset } => {
+                matched.extend(split_pieces(&self.path[offset..]).map(|(_, piece)| piece))
+            }
+            _ => {}
+        }
+        if matched
+            .iter()
+            .copied()
+            .eq(split_pieces(self.path).map(|(_, piece)| piece))
+        {
+            return Option::None;
         }
 
-        self
+        let mut rendered = String::new();
+        for piece in matched {
+            rendered.push_str(PATH_SEPARATOR);
+            rendered.push_str(piece);
+        }
+        if self.path.ends_with(PATH_SEPARATOR) {
+            rendered.push_str(PATH_SEPARATOR);
+        }
+
+        Option::Some(rendered)
     }
 }
 
-#[derive(Debug, Clone)]
-struct LookupResult<'a, T>
-where
-    T: Clone + Debug,
-{
-    item: &'a Node<T>,
-    name: String,
-    loop_behavior: LoopBehavior,
+/// A place where a walk of the tree can end, see [`Walk`].
+struct Terminal<'t, T: Clone + Debug> {
+    node: &'t Node<T>,
+    kind: TerminalKind<'t>,
 }
 
-impl<'a, T> LookupResult<'a, T>
+impl<'t, T> Terminal<'t, T>
 where
     T: Clone + Debug,
 {
-    fn create(item: &'a Node<T>, name: String, loop_behavior: LoopBehavior) -> Self {
-        LookupResult {
-            item,
-            name,
-            loop_behavior,
+    fn new(node: &'t Node<T>, kind: TerminalKind<'t>) -> Self {
+        Terminal { node, kind }
+    }
+
+    /// Returns the item of the method, a greedy node only matches with its greedy items.
+    fn get_item(&self, method: &Method) -> Option<&'t T> {
+        match self.kind {
+            TerminalKind::Greedy { .. } if !self.node.is_greedy(method) => Option::None,
+            _ => self.node.get_item(method),
+        }
+    }
+
+    fn get_priority(&self, method: &Method) -> i32 {
+        self.node.get_priority(method)
+    }
+
+    fn get_methods(&self) -> Vec<Method> {
+        match self.kind {
+            TerminalKind::Greedy { .. } => self.node.get_greedy_methods(),
+            _ => self.node.get_methods(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-enum LoopBehavior {
-    Ignore,
-    Collect,
-    Finish,
+// Clone and Copy are implemented by hand, since deriving them would require `T: Copy`.
+impl<'t, T: Clone + Debug> Clone for Terminal<'t, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-#[derive(Debug, Clone)]
-struct DynamicChild<T>
-where
-    T: Clone + Debug,
-{
-    name: String,
-    child_type: DynamicChildType<T>,
+impl<'t, T: Clone + Debug> Copy for Terminal<'t, T> {}
+
+#[derive(Debug, Clone, Copy)]
+enum TerminalKind<'t> {
+    /// the node of the last piece
+    Exact,
+    /// an optional parameter child of the node of the last piece, captured as an empty value
+    /// on a trailing slash
+    Optional { name: &'t str, empty: bool },
+    /// a wildcard capturing the rest of the path from the offset, or nothing
+    Wildcard {
+        name: &'t str,
+        offset: Option<usize>,
+    },
+    /// a greedy node leaving the rest of the path from the offset unconsumed
+    Greedy { offset: usize },
 }
 
-impl<T> DynamicChild<T>
-where
-    T: Clone + Debug,
-{
-    fn create(name: String, child_type: DynamicChildType<T>) -> Self {
-        DynamicChild { name, child_type }
-    }
+/// Splits a path into its non-empty pieces, along with their byte offsets in the path.
+fn split_pieces(path: &str) -> impl Iterator<Item = (usize, &str)> + Clone {
+    path.split(PATH_SEPARATOR)
+        .scan(0, |offset, piece| {
+            let start = *offset;
+            *offset += piece.len() + PATH_SEPARATOR.len();
+            Option::Some((start, piece))
+        })
+        .filter(|(_, piece)| !piece.is_empty())
+}
 
-    fn get_name(&self) -> &str {
-        &self.name
-    }
+/// Returns the rest of the path from the given offset, with the empty pieces left out.
+///
+/// The rest is sliced from the path, it is only rebuilt when it contains empty pieces.
+fn rest_of(path: &str, offset: usize, decode_params: bool) -> String {
+    let rest = path[offset..].trim_end_matches(PATH_SEPARATOR);
+    let rest = if rest.contains("//") {
+        Cow::Owned(
+            split_pieces(rest)
+                .map(|(_, piece)| piece)
+                .collect::<Vec<&str>>()
+                .join(PATH_SEPARATOR),
+        )
+    } else {
+        Cow::Borrowed(rest)
+    };
 
-    fn get_child_type(&self) -> &DynamicChildType<T> {
-        &self.child_type
+    if decode_params {
+        percent_decode_str(&rest).decode_utf8_lossy().into_owned()
+    } else {
+        rest.into_owned()
     }
+}
 
-    fn get_mut_child_type(&mut self) -> &mut DynamicChildType<T> {
-        &mut self.child_type
+/// Returns the rest of the path like [`rest_of`], with the end offsets of its segments.
+///
+/// The segments are decoded one by one, so an encoded separator stays inside its segment.
+fn wildcard_rest_of(path: &str, offset: usize, decode_params: bool) -> (String, Vec<usize>) {
+    let mut rest = String::new();
+    let mut ends = Vec::new();
+    for (_, piece) in split_pieces(&path[offset..]) {
+        if !ends.is_empty() {
+            rest.push_str(PATH_SEPARATOR);
+        }
+        if decode_params {
+            rest.push_str(&percent_decode_str(piece).decode_utf8_lossy());
+        } else {
+            rest.push_str(piece);
+        }
+        ends.push(rest.len());
     }
+
+    (rest, ends)
 }
 
 #[derive(Debug, Clone)]
-enum DynamicChildType<T>
-where
-    T: Debug + Clone,
-{
-    Parameter(Box<Node<T>>),
-    Wildcard(Box<Node<T>>),
+struct Node<T: Clone + Debug> {
+    static_children: Map<String, Box<Node<T>>>,
+    // The names of the static children by their lowercase form, for the case insensitive
+    // lookups. Of the names differing only in their casing, the smallest one is kept.
+    folded_static_names: Map<String, String>,
+    dynamic_children: Vec<DynamicChild<T>>,
+    item: SmallMap<Method, T>,
+    greedy: SmallMap<Method, bool>,
+    priorities: SmallMap<Method, i32>,
 }
 
-impl<T> DynamicChildType<T>
+impl<T> Node<T>
 where
     T: Clone + Debug,
 {
-    fn get_parameter(&self) -> Option<&Node<T>> {
-        match self {
-            DynamicChildType::Parameter(ref x) => Option::Some(x),
-            _ => Option::None,
+    pub fn new() -> Self {
+        Node {
+            static_children: Map::new(),
+            folded_static_names: Map::new(),
+            dynamic_children: Vec::new(),
+            item: SmallMap::new(),
+            greedy: SmallMap::new(),
+            priorities: SmallMap::new(),
         }
     }
 
-    fn get_wildcard(&self) -> Option<&Node<T>> {
-        match self {
-            DynamicChildType::Wildcard(ref x) => Option::Some(x),
-            _ => Option::None,
+    /// Returns the child for the item, creating it when needed.
+    ///
+    /// A dynamic child takes the highest route priority of the paths registered through it. A
+    /// conflicting dynamic child is handled by the policy: when it is replaced, the items of its
+    /// subtree are pushed to `displaced`, and when it is kept, no child is returned. A route
+    /// that no lookup could reach, or that would make a registered one unreachable, is rejected,
+    /// see [`check_reachable`](#method.check_reachable). `last` tells whether the route ends at
+    /// the child.
+    pub fn ensure(
+        &mut self,
+        item: &Item,
+        constraint: &Option<Constraint>,
+        route_priority: i32,
+        last: bool,
+        policy: ConflictPolicy,
+        displaced: &mut Vec<T>,
+    ) -> Result<Option<&mut Node<T>>, NodeError> {
+        let child_type = match item {
+            Item::Static(ref name) => {
+                if !self.static_children.contains_key(name.as_str()) {
+                    self.fold_static_name(name);
+                }
+                return Result::Ok(Option::Some(
+                    self.static_children
+                        .entry(String::from(name))
+                        .or_insert_with(|| Box::new(Node::new())),
+                ));
+            }
+            Item::Parameter(_) => DynamicChildType::Parameter(Box::new(Node::new())),
+            Item::OptionalParameter(_) => {
+                DynamicChildType::OptionalParameter(Box::new(Node::new()))
+            }
+            Item::Wildcard(_) => DynamicChildType::Wildcard(Box::new(Node::new())),
+        };
+
+        let position = match self
+            .dynamic_children
+            .iter()
+            .position(|child| child.is_for(item, constraint))
+        {
+            Option::Some(position) => {
+                let rank = (
+                    Reverse(route_priority),
+                    self.dynamic_children[position].get_priority(),
+                    position,
+                );
+                self.check_reachable(item, constraint, rank, last, Option::Some(position))?;
+                let child = &mut self.dynamic_children[position];
+                if child.get_route_priority() < route_priority {
+                    child.set_route_priority(route_priority);
+                }
+
+                position
+            }
+            Option::None => {
+                let mut child = DynamicChild::create(
+                    String::from(item.get_name()),
+                    String::from(item.get_parameter_name()),
+                    constraint.clone(),
+                    child_type,
+                );
+                child.set_route_priority(route_priority);
+                let priority = child.get_priority();
+                if let Option::Some(index) = self.dynamic_children.iter().position(|existing| {
+                    existing.get_priority() == priority && existing.get_constraint() == constraint
+                }) {
+                    match policy {
+                        ConflictPolicy::Reject => {
+                            return Result::Err(NodeError::ConflictingDynamicSegment {
+                                existing: self.dynamic_children[index].get_segment().to_string(),
+                                incoming: String::from(item.get_name()),
+                            });
+                        }
+                        ConflictPolicy::KeepExisting => return Result::Ok(Option::None),
+                        ConflictPolicy::ReplaceExisting => {
+                            let rank = (Reverse(route_priority), priority, usize::MAX);
+                            self.check_reachable(
+                                item,
+                                constraint,
+                                rank,
+                                last,
+                                Option::Some(index),
+                            )?;
+                            let removed = self.dynamic_children.remove(index);
+                            let mut items = Vec::new();
+                            removed.get_child_type().get().collect_items(&mut items);
+                            displaced.extend(items.into_iter().cloned());
+                        }
+                    }
+                } else {
+                    let rank = (Reverse(route_priority), priority, usize::MAX);
+                    self.check_reachable(item, constraint, rank, last, Option::None)?;
+                }
+
+                self.insert_dynamic_child(child)
+            }
+        };
+
+        Result::Ok(Option::Some(
+            self.dynamic_children[position]
+                .get_mut_child_type()
+                .get_mut(),
+        ))
+    }
+
+    /// Checks that a route entering a dynamic child is not shadowed by a sibling accepting every
+    /// segment, and does not shadow the routes of a sibling itself.
+    ///
+    /// Of two matching routes, the one of the higher priority wins, then the one of the more
+    /// specific child, then the one of the child added earlier, as given by the `rank` of the
+    /// entering route: its priority, the specificity and the position of its child. Since the
+    /// lookup backtracks, a child accepting every segment only shadows the routes of a sibling
+    /// it matches in full: a wildcard all of them, a parameter the ones ending at the sibling.
+    ///
+    /// The child at `skipped` is the one entered, or the one about to be replaced, so it is not
+    /// considered.
+    fn check_reachable(
+        &self,
+        item: &Item,
+        constraint: &Option<Constraint>,
+        rank: (Reverse<i32>, u8, usize),
+        last: bool,
+        skipped: Option<usize>,
+    ) -> Result<(), NodeError> {
+        let wildcard = matches!(item, Item::Wildcard(_));
+        let ends_here = last && !wildcard;
+
+        for (index, existing) in self.dynamic_children.iter().enumerate() {
+            if Option::Some(index) == skipped {
+                continue;
+            }
+            let existing_rank = |priority| (Reverse(priority), existing.get_priority(), index);
+            let node = existing.get_child_type().get();
+            let existing_wildcard =
+                matches!(existing.get_child_type(), DynamicChildType::Wildcard(_));
+
+            if existing.get_constraint().is_none() && (existing_wildcard || ends_here) {
+                if let Option::Some(priority) = node.get_own_priorities().max() {
+                    if existing_rank(priority) < rank {
+                        return Result::Err(NodeError::UnreachableRoute {
+                            shadowed_by: existing.get_segment().to_string(),
+                        });
+                    }
+                }
+            }
+
+            if constraint.is_none() {
+                let shadowed = if wildcard {
+                    node.get_min_priority()
+                } else if ends_here && !existing_wildcard {
+                    node.get_own_priorities().min()
+                } else {
+                    Option::None
+                };
+                if let Option::Some(priority) = shadowed {
+                    if rank < existing_rank(priority) {
+                        return Result::Err(NodeError::ShadowingRoute {
+                            shadowed: existing.get_segment().to_string(),
+                        });
+                    }
+                }
+            }
         }
+
+        Result::Ok(())
     }
 
-    fn get_mut(&mut self) -> &mut Node<T> {
-        match self {
-            DynamicChildType::Parameter(ref mut x) => x,
-            DynamicChildType::Wildcard(ref mut x) => x,
+    fn fold_static_name(&mut self, name: &str) {
+        let folded = self
+            .folded_static_names
+            .entry(name.to_ascii_lowercase())
+            .or_insert_with(|| String::from(name));
+        if name < folded.as_str() {
+            *folded = String::from(name);
         }
     }
-}
 
-/// Router tree errors.
-#[derive(Error, Debug, PartialEq)]
-pub enum TreeError {
-    /// The requested path is not found.
-    #[error("path not found: {path}")]
-    PathNotFound {
-        /// missing path
-        path: String,
-    },
-    /// The requested method is not found.
-    #[error("method not found {method}")]
-    MethodNotFound {
-        /// missing method
-        method: Method,
-    },
+    /// Forgets the name of a removed static child, falling back to another one of the same
+    /// lowercase form.
+    fn unfold_static_name(&mut self, name: &str) {
+        let lowercase = name.to_ascii_lowercase();
+        if self
+            .folded_static_names
+            .get(lowercase.as_str())
+            .map(String::as_str)
+            != Option::Some(name)
+        {
+            return;
+        }
+
+        match self
+            .static_children
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.eq_ignore_ascii_case(name))
+            .min()
+        {
+            Option::Some(key) => {
+                let key = key.clone();
+                self.folded_static_names.insert(lowercase, key);
+            }
+            Option::None => {
+                self.folded_static_names.remove(lowercase.as_str());
+            }
+        }
+    }
+
+    /// Inserts a dynamic child after the children of higher specificity and after the ones of
+    /// the same specificity that were added earlier.
+    ///
+    /// The route priority is not part of the order, since it belongs to the routes and not to
+    /// the children: the lookup prefers the routes of a higher priority when it walks them.
+    fn insert_dynamic_child(&mut self, child: DynamicChild<T>) -> usize {
+        let priority = child.get_priority();
+        let position = self
+            .dynamic_children
+            .iter()
+            .take_while(|existing| existing.get_priority() <= priority)
+            .count();
+        self.dynamic_children.insert(position, child);
+
+        position
+    }
+
+    pub fn set(&mut self, method: Method, item: T, greedy: bool, priority: i32) {
+        if greedy {
+            self.greedy.insert(method.clone(), true);
+        }
+        self.priorities.insert(method.clone(), priority);
+        self.item.insert(method, item);
+    }
+
+    /// Returns the route priority of the method, or of the item registered for any method.
+    pub fn get_priority(&self, method: &Method) -> i32 {
+        self.priorities
+            .get(method)
+            .or_else(|| {
+                self.priorities
+                    .iter()
+                    .find(|(method, _)| method.as_str() == ANY_METHOD)
+                    .map(|(_, priority)| priority)
+            })
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the route priorities of the items of the node.
+    fn get_own_priorities(&self) -> impl Iterator<Item = i32> + '_ {
+        self.priorities.iter().map(|(_, priority)| *priority)
+    }
+
+    /// Returns the lowest route priority of the paths registered in the subtree.
+    fn get_min_priority(&self) -> Option<i32> {
+        self.get_own_priorities()
+            .chain(
+                self.static_children
+                    .iter()
+                    .filter_map(|(_, child)| child.get_min_priority()),
+            )
+            .chain(
+                self.dynamic_children
+                    .iter()
+                    .filter_map(|child| child.get_child_type().get().get_min_priority()),
+            )
+            .min()
+    }
+
+    /// Returns the highest route priority of the paths registered in the subtree.
+    fn get_max_priority(&self) -> i32 {
+        self.get_own_priorities()
+            .chain(
+                self.static_children
+                    .iter()
+                    .map(|(_, child)| child.get_max_priority()),
+            )
+            .chain(
+                self.dynamic_children
+                    .iter()
+                    .map(|child| child.get_route_priority()),
+            )
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn has_greedy(&self) -> bool {
+        !self.greedy.is_empty()
+    }
+
+    pub fn get_greedy_methods(&self) -> Vec<Method> {
+        self.greedy
+            .iter()
+            .filter(|(_, greedy)| **greedy)
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+
+    pub fn is_greedy(&self, method: &Method) -> bool {
+        self.greedy.get(method).copied().unwrap_or(false)
+            || (!self.item.contains_key(method)
+                && self
+                    .greedy
+                    .iter()
+                    .any(|(method, greedy)| *greedy && method.as_str() == ANY_METHOD))
+    }
+
+    pub fn remove(
+        &mut self,
+        items: &[Item],
+        constraints: &[Option<Constraint>],
+        method: &Method,
+    ) -> Option<T> {
+        let (item, rest) = match items.split_first() {
+            Option::None => {
+                self.greedy.remove(method);
+                self.priorities.remove(method);
+                return self.item.remove(method);
+            }
+            Option::Some(split) => split,
+        };
+        let (constraint, rest_constraints) = constraints.split_first()?;
+
+        if let Item::Static(ref name) = item {
+            let child = self.static_children.get_mut(name)?;
+            let removed = child.remove(rest, rest_constraints, method);
+            if child.is_empty() {
+                self.static_children.remove(name);
+                self.unfold_static_name(name);
+            }
+
+            return removed;
+        }
+
+        let position = self
+            .dynamic_children
+            .iter()
+            .position(|child| child.is_for(item, constraint))?;
+        let node = self.dynamic_children[position]
+            .get_mut_child_type()
+            .get_mut();
+        let removed = node.remove(rest, rest_constraints, method);
+        if node.is_empty() {
+            self.dynamic_children.remove(position);
+        } else if removed.is_some() {
+            let route_priority = node.get_max_priority();
+            self.dynamic_children[position].set_route_priority(route_priority);
+        }
+
+        removed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.item.is_empty() && self.static_children.is_empty() && self.dynamic_children.is_empty()
+    }
+
+    pub fn has(&self, method: &Method) -> bool {
+        self.item.contains_key(method)
+    }
+
+    /// Returns the static child of the piece with its registered name.
+    ///
+    /// A case insensitive lookup falls back to the child of the same lowercase form, the one
+    /// with the smallest name when several differ only in their casing.
+    pub fn get_static_child(&self, name: &str, case_insensitive: bool) -> Option<(&str, &Node<T>)> {
+        self.static_children
+            .get_key_value(name)
+            .or_else(|| {
+                if case_insensitive {
+                    let key = self
+                        .folded_static_names
+                        .get(name.to_ascii_lowercase().as_str())?;
+                    self.static_children.get_key_value(key.as_str())
+                } else {
+                    Option::None
+                }
+            })
+            .map(|(key, child)| (key.as_str(), child.as_ref()))
+    }
+
+    pub fn get_wildcard_child(&self, piece: &str) -> Option<(&Node<T>, &str)> {
+        self.dynamic_children
+            .iter()
+            .filter(|child| child.accepts(piece))
+            .find_map(|child| {
+                child
+                    .get_child_type()
+                    .get_wildcard()
+                    .map(|node| (node, child.get_name()))
+            })
+    }
+
+    /// Returns the optional parameter child.
+    ///
+    /// On a trailing slash the parameter is captured as an empty value, so the child is only
+    /// returned when its constraint accepts an empty value.
+    pub fn get_optional_child(&self, trailing_slash: bool) -> Option<(&Node<T>, &str)> {
+        self.dynamic_children
+            .iter()
+            .filter(|child| !trailing_slash || child.accepts(""))
+            .find_map(|child| {
+                child
+                    .get_child_type()
+                    .get_optional_parameter()
+                    .map(|node| (node, child.get_name()))
+            })
+    }
+
+    pub fn get_methods(&self) -> Vec<Method> {
+        self.item.iter().map(|(method, _)| method.clone()).collect()
+    }
+
+    /// Returns the item of the method, or the item registered for any method.
+    pub fn get_item(&self, method: &Method) -> Option<&T> {
+        self.item.get(method).or_else(|| {
+            self.item
+                .iter()
+                .find(|(method, _)| method.as_str() == ANY_METHOD)
+                .map(|(_, item)| item)
+        })
+    }
+
+    pub fn optimize(&mut self) -> &Self {
+        self.static_children.optimize();
+        self.folded_static_names.optimize();
+        self.item.optimize();
+        self.greedy.optimize();
+        self.priorities.optimize();
+
+        for (_, v) in self.static_children.iter_mut() {
+            v.optimize();
+        }
+
+        self.dynamic_children.shrink_to_fit();
+        for dc in self.dynamic_children.iter_mut() {
+            dc.get_mut_child_type().get_mut().optimize();
+        }
+
+        self
+    }
+
+    fn walk<F>(&self, pattern: &mut String, f: &mut F)
+    where
+        F: FnMut(&str, &[Method]),
+    {
+        if !self.item.is_empty() {
+            let mut methods: Vec<Method> =
+                self.item.iter().map(|(method, _)| method.clone()).collect();
+            methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            f(
+                if pattern.is_empty() {
+                    PATH_SEPARATOR
+                } else {
+                    pattern
+                },
+                &methods,
+            );
+        }
+
+        let len = pattern.len();
+        let mut static_children: Vec<(&String, &Box<Node<T>>)> =
+            self.static_children.iter().collect();
+        static_children.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, child) in static_children {
+            pattern.push_str(PATH_SEPARATOR);
+            if name.starts_with([':', '*']) {
+                pattern.push(ESCAPE);
+            }
+            pattern.push_str(name);
+            child.walk(pattern, f);
+            pattern.truncate(len);
+        }
+        for child in self.dynamic_children.iter() {
+            pattern.push_str(PATH_SEPARATOR);
+            pattern.push_str(child.get_segment());
+            child.get_child_type().get().walk(pattern, f);
+            pattern.truncate(len);
+        }
+    }
+
+    fn collect_items<'a>(&'a self, items: &mut Vec<&'a T>) {
+        items.extend(self.item.iter().map(|(_, item)| item));
+
+        for (_, child) in self.static_children.iter() {
+            child.collect_items(items);
+        }
+        for child in self.dynamic_children.iter() {
+            child.get_child_type().get().collect_items(items);
+        }
+    }
+
+    /// Collects the items of the paths matching the pieces: deeper static children first, then
+    /// the dynamic children in their order, and the greedy items of the node last.
+    fn collect_matches<'a>(&'a self, pieces: &[&str], items: &mut Vec<&'a T>) {
+        let sorted = |node: &'a Node<T>, greedy_only: bool| {
+            let mut node_items: Vec<(&Method, &'a T)> = node
+                .item
+                .iter()
+                .filter(|(method, _)| {
+                    !greedy_only || node.greedy.get(method) == Option::Some(&true)
+                })
+                .collect();
+            node_items.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+            node_items.into_iter().map(|(_, item)| item)
+        };
+
+        let (piece, rest) = match pieces.split_first() {
+            Option::Some(split) => split,
+            Option::None => {
+                items.extend(sorted(self, false));
+                for child in self.dynamic_children.iter() {
+                    match child.get_child_type() {
+                        DynamicChildType::OptionalParameter(node)
+                        | DynamicChildType::Wildcard(node) => items.extend(sorted(node, false)),
+                        DynamicChildType::Parameter(_) => {}
+                    }
+                }
+                return;
+            }
+        };
+
+        if let Option::Some(child) = self.static_children.get(*piece) {
+            child.collect_matches(rest, items);
+        }
+        for child in self
+            .dynamic_children
+            .iter()
+            .filter(|child| child.accepts(piece))
+        {
+            match child.get_child_type() {
+                DynamicChildType::Wildcard(node) => items.extend(sorted(node, false)),
+                DynamicChildType::Parameter(node) | DynamicChildType::OptionalParameter(node) => {
+                    node.collect_matches(rest, items)
+                }
+            }
+        }
+        items.extend(sorted(self, true));
+    }
+
+    fn collect_stats(&self, depth: usize, dynamic: bool, stats: &mut RouterStats) {
+        stats.add_node(depth, dynamic, self.item.len());
+
+        for (_, child) in self.static_children.iter() {
+            child.collect_stats(depth + 1, false, stats);
+        }
+        for child in self.dynamic_children.iter() {
+            child
+                .get_child_type()
+                .get()
+                .collect_stats(depth + 1, true, stats);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DynamicChild<T>
+where
+    T: Clone + Debug,
+{
+    segment: String,
+    name: String,
+    constraint: Option<Constraint>,
+    route_priority: i32,
+    child_type: DynamicChildType<T>,
+}
+
+impl<T> DynamicChild<T>
+where
+    T: Clone + Debug,
+{
+    fn create(
+        segment: String,
+        name: String,
+        constraint: Option<Constraint>,
+        child_type: DynamicChildType<T>,
+    ) -> Self {
+        DynamicChild {
+            segment,
+            name,
+            constraint,
+            route_priority: 0,
+            child_type,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_segment(&self) -> &str {
+        &self.segment
+    }
+
+    fn accepts(&self, piece: &str) -> bool {
+        self.constraint
+            .as_ref()
+            .is_none_or(|constraint| constraint.matches(piece))
+    }
+
+    fn get_child_type(&self) -> &DynamicChildType<T> {
+        &self.child_type
+    }
+
+    fn get_constraint(&self) -> &Option<Constraint> {
+        &self.constraint
+    }
+
+    /// Dynamic children are tried in ascending priority: constrained parameters first, then
+    /// unconstrained parameters, then wildcards. Static children always come before all of them.
+    fn get_priority(&self) -> u8 {
+        match (&self.child_type, &self.constraint) {
+            (DynamicChildType::Wildcard(_), _) => 2,
+            (_, Option::Some(_)) => 0,
+            (_, Option::None) => 1,
+        }
+    }
+
+    fn get_route_priority(&self) -> i32 {
+        self.route_priority
+    }
+
+    fn set_route_priority(&mut self, route_priority: i32) {
+        self.route_priority = route_priority;
+    }
+
+    fn is_for(&self, item: &Item, constraint: &Option<Constraint>) -> bool {
+        let same_type = matches!(
+            (&self.child_type, item),
+            (DynamicChildType::Parameter(_), Item::Parameter(_))
+                | (
+                    DynamicChildType::OptionalParameter(_),
+                    Item::OptionalParameter(_)
+                )
+                | (DynamicChildType::Wildcard(_), Item::Wildcard(_))
+        );
+
+        same_type && self.name == item.get_parameter_name() && self.constraint == *constraint
+    }
+
+    fn get_mut_child_type(&mut self) -> &mut DynamicChildType<T> {
+        &mut self.child_type
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DynamicChildType<T>
+where
+    T: Debug + Clone,
+{
+    Parameter(Box<Node<T>>),
+    OptionalParameter(Box<Node<T>>),
+    Wildcard(Box<Node<T>>),
+}
+
+impl<T> DynamicChildType<T>
+where
+    T: Clone + Debug,
+{
+    fn get_optional_parameter(&self) -> Option<&Node<T>> {
+        match self {
+            DynamicChildType::OptionalParameter(ref x) => Option::Some(x),
+            _ => Option::None,
+        }
+    }
+
+    fn get_wildcard(&self) -> Option<&Node<T>> {
+        match self {
+            DynamicChildType::Wildcard(ref x) => Option::Some(x),
+            _ => Option::None,
+        }
+    }
+
+    fn get(&self) -> &Node<T> {
+        match self {
+            DynamicChildType::Parameter(ref x) => x,
+            DynamicChildType::OptionalParameter(ref x) => x,
+            DynamicChildType::Wildcard(ref x) => x,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut Node<T> {
+        match self {
+            DynamicChildType::Parameter(ref mut x) => x,
+            DynamicChildType::OptionalParameter(ref mut x) => x,
+            DynamicChildType::Wildcard(ref mut x) => x,
+        }
+    }
+}
+
+/// Router tree errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum TreeError {
+    /// The requested path is not found.
+    #[error("path not found: {path}")]
+    PathNotFound {
+        /// missing path
+        path: String,
+    },
+    /// The requested method is not found.
+    #[error("method not found {method}")]
+    MethodNotFound {
+        /// missing method
+        method: Method,
+        /// methods registered for the path
+        allowed: Vec<Method>,
+    },
     /// The given route is already registered.
     #[error("path already registered: {route}")]
     PathAlreadyRegistered {
         /// already registered route
         route: String,
     },
+    /// Another parameter or wildcard is already registered at the same position.
+    #[error("conflicting dynamic segment: {incoming} conflicts with {existing}")]
+    ConflictingDynamicSegment {
+        /// segment of the registered route
+        existing: String,
+        /// segment of the added route
+        incoming: String,
+    },
+    /// The route could never be resolved, because a segment accepting every value is tried
+    /// before its parameter.
+    #[error("unreachable route: {route} is shadowed by {shadowed_by}")]
+    UnreachableRoute {
+        /// the added route
+        route: String,
+        /// segment of the registered route that is tried first
+        shadowed_by: String,
+    },
+    /// The route would make registered routes unreachable, because its segment accepts every
+    /// value and is tried before theirs.
+    #[error("shadowing route: {route} shadows the routes of {shadowed}")]
+    ShadowingRoute {
+        /// the added route
+        route: String,
+        /// segment of the registered routes that would not be resolved anymore
+        shadowed: String,
+    },
 }
 
 #[derive(Error, Debug, PartialEq)]
 enum NodeError {
-    #[error("route already registered")]
-    PathAlreadyRegistered,
+    #[error("conflicting dynamic segment: {incoming} conflicts with {existing}")]
+    ConflictingDynamicSegment { existing: String, incoming: String },
+    #[error("unreachable route: shadowed by {shadowed_by}")]
+    UnreachableRoute { shadowed_by: String },
+    #[error("shadowing route: shadows {shadowed}")]
+    ShadowingRoute { shadowed: String },
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::Tree;
+    use super::TreeError;
     use crate::path::Path;
+    use crate::{ConflictPolicy, LookupOptions, ANY_METHOD, WILDCARD_PARAMETER};
+    use crate::{TraceBranch, TraceStep};
     use http::Method;
     use rand::Rng;
     use rstest::*;
 
     fn path(p: &str) -> Path {
-        Path::parse(Method::GET, p).unwrap()
+        Path::parse_get(p).unwrap()
     }
 
     #[rstest]
@@ -451,6 +1647,33 @@ mod tests {
         assert!(tree.lookup(&Method::POST, "/").is_err());
     }
 
+    #[rstest]
+    fn test_method_not_found_allowed() {
+        let mut tree = Tree::new();
+
+        assert!(tree.add(path("/item"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/item").unwrap(), 2)
+            .is_ok());
+        assert!(tree.add(path("/nested/item"), 3).is_ok());
+
+        let tree = tree;
+
+        assert_eq!(
+            tree.lookup(&Method::DELETE, "/item").unwrap_err(),
+            TreeError::MethodNotFound {
+                method: Method::DELETE,
+                allowed: vec![Method::GET, Method::POST],
+            }
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/nested").unwrap_err(),
+            TreeError::PathNotFound {
+                path: String::from("/nested"),
+            }
+        );
+    }
+
     #[rstest]
     fn test_route_already_registered() {
         let mut rng = rand::thread_rng();
@@ -473,8 +1696,11 @@ mod tests {
             tree.lookup(&Method::GET, "/static").unwrap().get_item(),
             &static_item_2
         );
-        assert!(tree.add(path("/wildcard/:item"), param_item_2).is_err());
-        assert_ne!(
+        assert!(tree.add(path("/parameter/:other"), param_item_2).is_err());
+        assert!(tree.add(path("/wildcard/*other"), wildcard_item_2).is_err());
+
+        assert!(tree.add(path("/wildcard/:item"), param_item_2).is_ok());
+        assert_eq!(
             tree.lookup(&Method::GET, "/wildcard/foo")
                 .unwrap()
                 .get_item(),
@@ -482,12 +1708,1144 @@ mod tests {
         );
         assert!(tree
             .add(path("/parameter/*wildcard"), wildcard_item_2)
-            .is_err());
-        assert_ne!(
+            .is_ok());
+        assert_eq!(
             tree.lookup(&Method::GET, "/parameter/foo")
                 .unwrap()
                 .get_item(),
+            &param_item
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/parameter/foo/bar")
+                .unwrap()
+                .get_item(),
             &wildcard_item_2
         );
     }
+
+    #[rstest]
+    fn test_greedy() {
+        let mut rng = rand::thread_rng();
+
+        let greedy_item: u64 = rng.gen();
+        let static_item: u64 = rng.gen();
+
+        let mut greedy_path = path("/files/:name");
+        greedy_path.set_greedy(true);
+
+        let mut tree = Tree::new();
+        assert!(tree.add(greedy_path, greedy_item).is_ok());
+        assert!(tree.add(path("/files"), static_item).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        let exact = tree.lookup(m, "/files/doc").unwrap();
+        assert_eq!(exact.get_item(), &greedy_item);
+        assert_eq!(exact.remainder(), Option::None);
+
+        let longer = tree.lookup(m, "/files/doc/page/2").unwrap();
+        assert_eq!(longer.get_item(), &greedy_item);
+        assert_eq!(longer.get_params().get("name").unwrap(), "doc");
+        assert_eq!(longer.remainder(), Option::Some("/page/2"));
+
+        let root = tree.lookup(m, "/files").unwrap();
+        assert_eq!(root.get_item(), &static_item);
+        assert_eq!(root.remainder(), Option::None);
+
+        assert!(tree.lookup(&Method::POST, "/files/doc/page").is_err());
+    }
+
+    #[rstest]
+    fn test_optional_trailing_slash() {
+        let mut rng = rand::thread_rng();
+
+        let page_item: u64 = rng.gen();
+        let trailing_item: u64 = rng.gen();
+        let optional_item: u64 = rng.gen();
+
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/page"), page_item).is_ok());
+        assert!(tree.add(path("/page/:trailing?"), trailing_item).is_ok());
+        assert!(tree.add(path("/other/:trailing?"), optional_item).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        let page = tree.lookup(m, "/page").unwrap();
+        assert_eq!(page.get_item(), &page_item);
+        assert!(page.get_params().get("trailing").is_none());
+
+        let trailing = tree.lookup(m, "/page/").unwrap();
+        assert_eq!(trailing.get_item(), &trailing_item);
+        assert_eq!(trailing.get_params().get("trailing").unwrap(), "");
+
+        let other = tree.lookup(m, "/other").unwrap();
+        assert_eq!(other.get_item(), &optional_item);
+        assert!(other.get_params().get("trailing").is_none());
+
+        assert_eq!(
+            tree.lookup(m, "/other/")
+                .unwrap()
+                .get_params()
+                .get("trailing")
+                .unwrap(),
+            ""
+        );
+        assert_eq!(
+            tree.lookup(m, "/other/foo")
+                .unwrap()
+                .get_params()
+                .get("trailing")
+                .unwrap(),
+            "foo"
+        );
+        assert!(tree.lookup(m, "/other/foo/bar").is_err());
+    }
+
+    #[rstest]
+    fn test_optional_trailing_slash_constraint() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/page/:tab(a|b)?"), 1).is_ok());
+        assert!(tree.add(path("/other/:tab(a|b|)?"), 2).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        assert!(tree
+            .lookup(m, "/page")
+            .unwrap()
+            .get_params()
+            .get("tab")
+            .is_none());
+        assert_eq!(
+            tree.lookup(m, "/page/").unwrap_err(),
+            TreeError::PathNotFound {
+                path: String::from("/page/")
+            }
+        );
+        assert!(!tree.contains(m, "/page/", &LookupOptions::default()));
+        assert!(tree
+            .allowed_methods("/page/", &LookupOptions::default())
+            .is_err());
+        assert_eq!(
+            tree.lookup(m, "/page/a").unwrap().get_params().get("tab"),
+            Option::Some(&String::from("a"))
+        );
+        assert_eq!(
+            tree.lookup(m, "/other/").unwrap().get_params().get("tab"),
+            Option::Some(&String::new())
+        );
+    }
+
+    #[rstest]
+    fn test_optional_segment() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:id/:tab?"), 1).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        let without = tree.lookup(m, "/users/5").unwrap();
+        assert_eq!(without.get_item(), &1);
+        assert_eq!(without.get_params().get("id").unwrap(), "5");
+        assert!(without.get_params().get("tab").is_none());
+
+        let with = tree.lookup(m, "/users/5/profile").unwrap();
+        assert_eq!(with.get_item(), &1);
+        assert_eq!(with.get_params().get("tab").unwrap(), "profile");
+    }
+
+    #[rstest]
+    fn test_constraint() {
+        let mut tree = Tree::new();
+        assert!(tree
+            .add(path("/strict/:status(active|inactive)"), 1)
+            .is_ok());
+        assert!(tree
+            .add(path("/relaxed/:status(active|inactive)i"), 2)
+            .is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        assert_eq!(tree.lookup(m, "/strict/active").unwrap().get_item(), &1);
+        assert!(tree.lookup(m, "/strict/Active").is_err());
+        assert!(tree.lookup(m, "/strict/deleted").is_err());
+
+        let relaxed = tree.lookup(m, "/relaxed/Active").unwrap();
+        assert_eq!(relaxed.get_item(), &2);
+        assert_eq!(relaxed.get_params().get("status").unwrap(), "Active");
+        assert!(tree.lookup(m, "/relaxed/deleted").is_err());
+    }
+
+    #[rstest]
+    fn test_lookup_case_insensitive() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:id/Profile"), 1).is_ok());
+        assert!(tree.add(path("/files/*path"), 2).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        assert!(tree.lookup(m, "/Users/123/profile").is_err());
+
+        let mut case_insensitive = LookupOptions::new();
+        case_insensitive.set_case_insensitive(true);
+
+        let (route_match, canonical) = tree
+            .lookup_with(m, "/Users/AbC/profile/", &case_insensitive)
+            .unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(route_match.get_params().get("id").unwrap(), "AbC");
+        assert_eq!(canonical.unwrap(), "/users/AbC/Profile/");
+
+        let (_, canonical) = tree
+            .lookup_with(m, "/users/AbC/Profile", &case_insensitive)
+            .unwrap();
+        assert!(canonical.is_none());
+
+        let (route_match, canonical) = tree
+            .lookup_with(m, "/FILES/Foo/Bar", &case_insensitive)
+            .unwrap();
+        assert_eq!(route_match.get_params().get("path").unwrap(), "Foo/Bar");
+        assert_eq!(canonical.unwrap(), "/files/Foo/Bar");
+    }
+
+    #[rstest(
+        names,
+        case(&["Files", "FILES", "files"]),
+        case(&["files", "FILES", "Files"]),
+        case(&["FILES", "files", "Files"])
+    )]
+    fn test_lookup_case_insensitive_colliding(names: &[&str]) {
+        let mut tree = Tree::new();
+        for name in names {
+            assert!(tree.add(path(&format!("/{}/:id", name)), 1).is_ok());
+        }
+        let mut case_insensitive = LookupOptions::new();
+        case_insensitive.set_case_insensitive(true);
+        let m = &Method::GET;
+
+        let (_, canonical) = tree.lookup_with(m, "/fiLes/1", &case_insensitive).unwrap();
+        assert_eq!(canonical.unwrap(), "/FILES/1");
+        let (_, canonical) = tree.lookup_with(m, "/Files/1", &case_insensitive).unwrap();
+        assert!(canonical.is_none());
+
+        assert!(tree.remove(&path("/FILES/:id")).is_ok());
+        let (_, canonical) = tree.lookup_with(m, "/fiLes/1", &case_insensitive).unwrap();
+        assert_eq!(canonical.unwrap(), "/Files/1");
+        assert!(tree.remove(&path("/Files/:id")).is_ok());
+        assert!(tree.remove(&path("/files/:id")).is_ok());
+        assert!(tree.lookup_with(m, "/fiLes/1", &case_insensitive).is_err());
+    }
+
+    #[rstest]
+    fn test_remove() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/users").unwrap(), 2)
+            .is_ok());
+        assert!(tree.add(path("/users/:id/posts"), 3).is_ok());
+        assert!(tree.add(path("/files/*path"), 4).is_ok());
+
+        assert_eq!(tree.remove(&path("/users/:id/posts")).unwrap(), 3);
+        assert!(tree.lookup(&Method::GET, "/users/5/posts").is_err());
+        assert!(tree
+            .root
+            .static_children
+            .get("users")
+            .unwrap()
+            .dynamic_children
+            .is_empty());
+        assert!(tree.remove(&path("/users/:id/posts")).is_err());
+
+        assert!(tree.remove(&path("/files/:path")).is_err());
+        assert_eq!(tree.remove(&path("/files/*path")).unwrap(), 4);
+        assert!(tree.root.static_children.get("files").is_none());
+
+        assert_eq!(tree.remove(&path("/users")).unwrap(), 1);
+        assert!(tree.lookup(&Method::GET, "/users").is_err());
+        assert_eq!(tree.lookup(&Method::POST, "/users").unwrap().get_item(), &2);
+
+        assert!(tree.add(path("/users/:name/posts"), 5).is_ok());
+        assert_eq!(
+            tree.lookup(&Method::GET, "/users/foo/posts")
+                .unwrap()
+                .get_item(),
+            &5
+        );
+    }
+
+    #[rstest]
+    fn test_decode_params() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/:name"), 1).is_ok());
+        assert!(tree.add(path("/my dir/*path"), 2).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        let mut decode = LookupOptions::new();
+        decode.set_decode_params(true);
+
+        assert_eq!(
+            tree.lookup(m, "/files/my%20file.txt")
+                .unwrap()
+                .get_params()
+                .get("name")
+                .unwrap(),
+            "my%20file.txt"
+        );
+
+        let (route_match, _) = tree
+            .lookup_with(m, "/files/my%20file.txt", &decode)
+            .unwrap();
+        assert_eq!(route_match.get_params().get("name").unwrap(), "my file.txt");
+
+        let (route_match, _) = tree
+            .lookup_with(m, "/my%20dir/a%2Fb/c%20d", &decode)
+            .unwrap();
+        assert_eq!(route_match.get_item(), &2);
+        assert_eq!(route_match.get_params().get("path").unwrap(), "a/b/c d");
+
+        assert!(tree.lookup_with(m, "/files%2Ffoo", &decode).is_err());
+    }
+
+    #[rstest]
+    fn test_regex_constraint() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:name"), 1).is_ok());
+        assert!(tree.add(path("/users/:id(\\d+)"), 2).is_ok());
+        assert!(tree.add(path("/users/:id(\\d+)/posts"), 3).is_ok());
+        assert!(tree.add(path("/users/:id(\\d+)"), 4).is_err());
+        assert!(tree.add(path("/users/:other"), 5).is_err());
+        assert!(tree.add(path("/users/:other(\\d+)"), 6).is_err());
+        assert!(tree.add(path("/users/:other?"), 7).is_err());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        let numeric = tree.lookup(m, "/users/123").unwrap();
+        assert_eq!(numeric.get_item(), &2);
+        assert_eq!(numeric.get_params().get("id").unwrap(), "123");
+        assert_eq!(tree.lookup(m, "/users/123/posts").unwrap().get_item(), &3);
+
+        let named = tree.lookup(m, "/users/abc").unwrap();
+        assert_eq!(named.get_item(), &1);
+        assert_eq!(named.get_params().get("name").unwrap(), "abc");
+        assert!(tree.lookup(m, "/users/abc/posts").is_err());
+    }
+
+    #[rstest]
+    fn test_dynamic_children_priority() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/*rest"), 1).is_ok());
+        assert!(tree.add(path("/files/:name"), 2).is_ok());
+        assert!(tree.add(path("/files/:id(\\d+)"), 3).is_ok());
+        assert!(tree.add(path("/files/new"), 4).is_ok());
+        assert!(tree.add(path("/files/:name/raw"), 5).is_ok());
+        assert!(tree.add(path("/files/*other"), 6).is_err());
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        assert_eq!(tree.lookup(m, "/files/new").unwrap().get_item(), &4);
+        assert_eq!(tree.lookup(m, "/files/42").unwrap().get_item(), &3);
+        assert_eq!(tree.lookup(m, "/files/readme").unwrap().get_item(), &2);
+        assert_eq!(tree.lookup(m, "/files/readme/raw").unwrap().get_item(), &5);
+
+        let rest = tree.lookup(m, "/files/docs/readme").unwrap();
+        assert_eq!(rest.get_item(), &1);
+        assert_eq!(rest.get_params().get("rest").unwrap(), "docs/readme");
+        assert!(rest.get_params().get("name").is_none());
+    }
+
+    #[rstest]
+    fn test_allowed_methods() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/users").unwrap(), 2)
+            .is_ok());
+        assert!(tree
+            .add(Path::parse(Method::DELETE, "/users/:id").unwrap(), 3)
+            .is_ok());
+        assert!(tree.add(path("/posts/:slug?"), 4).is_ok());
+
+        let tree = tree;
+        let options = LookupOptions::default();
+
+        assert_eq!(
+            tree.allowed_methods("/users", &options).unwrap(),
+            vec![Method::GET, Method::POST]
+        );
+        assert_eq!(
+            tree.allowed_methods("/users/1", &options).unwrap(),
+            vec![Method::DELETE]
+        );
+        assert_eq!(
+            tree.allowed_methods("/posts", &options).unwrap(),
+            vec![Method::GET]
+        );
+        assert!(tree.allowed_methods("/", &options).is_err());
+        assert!(tree.allowed_methods("/comments", &options).is_err());
+    }
+
+    #[rstest]
+    fn test_contains() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/"), 0).is_ok());
+        assert!(tree.add(path("/users/:id(\\d+)"), 1).is_ok());
+        assert!(tree.add(path("/users/:name"), 2).is_ok());
+        assert!(tree.add(path("/users/:name/posts/:slug?"), 3).is_ok());
+        assert!(tree.add(path("/files/*rest"), 4).is_ok());
+        assert!(tree.add(path("/files/:name/raw"), 5).is_ok());
+        let mut greedy_path = path("/static");
+        greedy_path.set_greedy(true);
+        assert!(tree.add(greedy_path, 6).is_ok());
+
+        let tree = tree;
+        let options = LookupOptions::default();
+
+        for method in &[Method::GET, Method::POST] {
+            for p in &[
+                "/",
+                "/users",
+                "/users/1",
+                "/users/abc",
+                "/users/abc/posts",
+                "/users/abc/posts/",
+                "/users/abc/posts/hello",
+                "/users/abc/comments",
+                "/files/a",
+                "/files/a/raw",
+                "/files/a/b/c",
+                "/static/css/main.css",
+                "/missing",
+            ] {
+                assert_eq!(
+                    tree.contains(method, p, &options),
+                    tree.lookup(method, p).is_ok(),
+                    "{} {}",
+                    method,
+                    p
+                );
+            }
+        }
+    }
+
+    #[rstest]
+    fn test_unnamed_wildcard() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/static/*"), 1).is_ok());
+        assert!(tree.add(path("/static/*rest"), 2).is_err());
+
+        let tree = tree;
+        let route_match = tree.lookup(&Method::GET, "/static/css/main.css").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(
+            route_match.wildcard_remainder(),
+            Option::Some("css/main.css")
+        );
+        assert_eq!(
+            route_match.get_params().get(WILDCARD_PARAMETER).unwrap(),
+            "css/main.css"
+        );
+    }
+
+    #[rstest(
+        paths,
+        case(&["/assets/*path", "/assets/:file", "/assets/index.html"]),
+        case(&["/assets/index.html", "/assets/:file", "/assets/*path"]),
+        case(&["/assets/:file", "/assets/*path", "/assets/index.html"])
+    )]
+    fn test_child_priority_ignores_insertion_order(paths: &[&str]) {
+        let mut tree = Tree::new();
+        for p in paths {
+            assert!(tree.add(path(p), String::from(*p)).is_ok());
+        }
+
+        let tree = tree;
+        let m = &Method::GET;
+
+        assert_eq!(
+            tree.lookup(m, "/assets/index.html").unwrap().get_item(),
+            "/assets/index.html"
+        );
+        assert_eq!(
+            tree.lookup(m, "/assets/app.js").unwrap().get_item(),
+            "/assets/:file"
+        );
+        assert_eq!(
+            tree.lookup(m, "/assets/js/app.js").unwrap().get_item(),
+            "/assets/*path"
+        );
+    }
+
+    #[rstest]
+    fn test_stats() {
+        assert_eq!(Tree::<u64>::new().stats().get_node_count(), 1);
+
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/users").unwrap(), 2)
+            .is_ok());
+        assert!(tree.add(path("/users"), 3).is_ok());
+        assert!(tree.add(path("/users/:id"), 4).is_ok());
+        assert!(tree.add(path("/users/:id/files/*path"), 5).is_ok());
+
+        let stats = tree.stats();
+        assert_eq!(stats.get_node_count(), 5);
+        assert_eq!(stats.get_max_depth(), 4);
+        assert_eq!(stats.get_route_count(), 5);
+        assert_eq!(stats.get_dynamic_node_count(), 2);
+    }
+
+    #[rstest]
+    fn test_any_method() {
+        let any = Method::from_bytes(ANY_METHOD.as_bytes()).unwrap();
+        let mut tree = Tree::new();
+        assert!(tree
+            .add(Path::parse(any.clone(), "/proxy/*rest").unwrap(), 1)
+            .is_ok());
+        assert!(tree
+            .add(Path::parse(Method::DELETE, "/proxy/*rest").unwrap(), 2)
+            .is_ok());
+        let mut greedy = Path::parse(any, "/api").unwrap();
+        greedy.set_greedy(true);
+        assert!(tree.add(greedy, 3).is_ok());
+
+        let tree = tree;
+        let options = LookupOptions::default();
+
+        assert_eq!(
+            tree.lookup(&Method::GET, "/proxy/a").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(
+            tree.lookup(&Method::PATCH, "/proxy/a").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(
+            tree.lookup(&Method::DELETE, "/proxy/a").unwrap().get_item(),
+            &2
+        );
+        assert_eq!(tree.lookup(&Method::PUT, "/api/v1").unwrap().get_item(), &3);
+        assert!(tree.contains(&Method::POST, "/proxy/a", &options));
+        assert!(tree.lookup(&Method::GET, "/other").is_err());
+    }
+
+    #[rstest]
+    fn test_wildcard_remainder_normalization() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/*path"), 1).is_ok());
+        let mut greedy = path("/docs");
+        greedy.set_greedy(true);
+        assert!(tree.add(greedy, 2).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+        let mut options = LookupOptions::default();
+        options.set_decode_params(true);
+
+        for (p, expected) in [
+            ("/files/a/b.txt", "a/b.txt"),
+            ("/files/a//b.txt/", "a/b.txt"),
+            ("//files///a/b.txt//", "a/b.txt"),
+        ] {
+            assert_eq!(
+                tree.lookup(m, p).unwrap().get_params().get("path").unwrap(),
+                expected
+            );
+        }
+        assert_eq!(
+            tree.lookup_with(m, "/files/my%20dir//a%2Fb", &options)
+                .unwrap()
+                .0
+                .get_params()
+                .get("path")
+                .unwrap(),
+            "my dir/a/b"
+        );
+        assert_eq!(
+            tree.lookup_with(m, "/docs/v1//intro%20page/", &options)
+                .unwrap()
+                .0
+                .remainder(),
+            Option::Some("/v1/intro%20page")
+        );
+    }
+
+    #[rstest]
+    fn test_escaped_static() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/\\:version"), 1).is_ok());
+        assert!(tree.add(path("/:name"), 2).is_ok());
+
+        let tree = tree;
+        assert_eq!(
+            tree.lookup(&Method::GET, "/:version").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(tree.lookup(&Method::GET, "/v1").unwrap().get_item(), &2);
+    }
+
+    #[rstest(
+        policy,
+        result,
+        item,
+        case(ConflictPolicy::ReplaceExisting, vec![1], 2),
+        case(ConflictPolicy::KeepExisting, vec![2], 1)
+    )]
+    fn test_conflict_policy_duplicate(policy: ConflictPolicy, result: Vec<i32>, item: i32) {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:id"), 1).is_ok());
+        assert_eq!(
+            tree.add(path("/users/:id"), 2).unwrap_err(),
+            TreeError::PathAlreadyRegistered {
+                route: String::from("users/:id")
+            }
+        );
+
+        assert_eq!(
+            tree.add_with(path("/users/:id"), 2, policy).unwrap(),
+            result
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/users/5").unwrap().get_item(),
+            &item
+        );
+    }
+
+    #[rstest]
+    fn test_conflict_policy_dynamic_segment() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:id"), 1).is_ok());
+        assert!(tree.add(path("/users/:id/posts"), 2).is_ok());
+
+        assert_eq!(
+            tree.add_with(path("/users/:name"), 3, ConflictPolicy::KeepExisting)
+                .unwrap(),
+            vec![3]
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/users/5")
+                .unwrap()
+                .get_params()
+                .get("id")
+                .unwrap(),
+            "5"
+        );
+
+        let mut displaced = tree
+            .add_with(path("/users/:name"), 3, ConflictPolicy::ReplaceExisting)
+            .unwrap();
+        displaced.sort();
+        assert_eq!(displaced, vec![1, 2]);
+        let route_match = tree.lookup(&Method::GET, "/users/foo").unwrap();
+        assert_eq!(route_match.get_item(), &3);
+        assert_eq!(route_match.get_params().get("name").unwrap(), "foo");
+        assert!(tree.lookup(&Method::GET, "/users/foo/posts").is_err());
+    }
+
+    #[rstest]
+    fn test_walk() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/"), 0).is_ok());
+        assert!(tree.add(path("/users/:id"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::PUT, "/users/:id").unwrap(), 2)
+            .is_ok());
+        assert!(tree.add(path("/users/*rest"), 3).is_ok());
+        assert!(tree.add(path("/api/\\:version"), 4).is_ok());
+        assert!(tree.add(path("/api/v1/users"), 5).is_ok());
+
+        let mut visited: Vec<(String, Vec<Method>)> = Vec::new();
+        tree.walk(|pattern, methods| visited.push((String::from(pattern), methods.to_vec())));
+
+        assert_eq!(
+            visited,
+            vec![
+                (String::from("/"), vec![Method::GET]),
+                (String::from("/api/\\:version"), vec![Method::GET]),
+                (String::from("/api/v1/users"), vec![Method::GET]),
+                (String::from("/users/:id"), vec![Method::GET, Method::PUT]),
+                (String::from("/users/*rest"), vec![Method::GET]),
+            ]
+        );
+    }
+
+    #[rstest(
+        first,
+        second,
+        item,
+        case(0, 0, 1),
+        case(0, 1, 2),
+        case(-1, 0, 2),
+        case(2, 1, 1)
+    )]
+    fn test_route_priority(first: i32, second: i32, item: i32) {
+        let mut tree = Tree::new();
+        let mut numeric = path("/items/:id(\\d+)");
+        numeric.set_priority(first);
+        let mut hex = path("/items/:code([0-9a-f]+)");
+        hex.set_priority(second);
+        assert!(tree.add(numeric, 1).is_ok());
+        assert!(tree.add(hex, 2).is_ok());
+
+        assert_eq!(
+            tree.lookup(&Method::GET, "/items/42").unwrap().get_item(),
+            &item
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/items/beef").unwrap().get_item(),
+            &2
+        );
+    }
+
+    #[rstest]
+    fn test_unreachable_route() {
+        let mut tree = Tree::new();
+        let mut wildcard = path("/files/*path");
+        wildcard.set_priority(1);
+        assert!(tree.add(wildcard, 1).is_ok());
+        assert!(tree.add(path("/files/readme"), 2).is_ok());
+        assert_eq!(
+            tree.add(path("/files/:id"), 3).unwrap_err(),
+            TreeError::UnreachableRoute {
+                route: String::from("files/:id"),
+                shadowed_by: String::from("*path"),
+            }
+        );
+        let mut raised = path("/files/:id/meta");
+        raised.set_priority(1);
+        assert!(tree.add(raised, 4).is_ok());
+
+        let mut slug = path("/items/:slug");
+        slug.set_priority(1);
+        assert!(tree.add(slug, 5).is_ok());
+        assert!(matches!(
+            tree.add(path("/items/:id(\\d+)"), 6),
+            Result::Err(TreeError::UnreachableRoute { .. })
+        ));
+        assert!(tree.add(path("/other/*path"), 7).is_ok());
+        assert!(tree.add(path("/other/:id"), 8).is_ok());
+
+        assert_eq!(
+            tree.lookup(&Method::GET, "/files/readme")
+                .unwrap()
+                .get_item(),
+            &2
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/files/42").unwrap().get_item(),
+            &1
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/files/42/meta")
+                .unwrap()
+                .get_item(),
+            &4
+        );
+        assert_eq!(
+            tree.lookup(&Method::GET, "/other/42").unwrap().get_item(),
+            &8
+        );
+    }
+
+    #[rstest(
+        first,
+        second,
+        shadowed,
+        case("/items/:id(\\d+)", "/items/:slug", ":id(\\d+)"),
+        case("/items/:id(\\d+)/edit", "/items/*rest", ":id(\\d+)"),
+        case("/items/:id", "/items/*rest", ":id")
+    )]
+    fn test_shadowing_route(first: &str, second: &str, shadowed: &str) {
+        let mut tree = Tree::new();
+        assert!(tree.add(path(first), 1).is_ok());
+        let mut shadowing = path(second);
+        shadowing.set_priority(1);
+        assert_eq!(
+            tree.add(shadowing.clone(), 2).unwrap_err(),
+            TreeError::ShadowingRoute {
+                route: shadowing.render_original(),
+                shadowed: String::from(shadowed),
+            }
+        );
+
+        let mut tree = Tree::new();
+        assert!(tree.add(shadowing, 2).is_ok());
+        assert!(matches!(
+            tree.add(path(first), 1),
+            Result::Err(TreeError::UnreachableRoute { .. })
+        ));
+    }
+
+    #[rstest]
+    fn test_shadowing_route_backtracks() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/items/:id(\\d+)/edit"), 1).is_ok());
+        let mut slug = path("/items/:slug");
+        slug.set_priority(1);
+        assert!(tree.add(slug, 2).is_ok());
+        assert!(matches!(
+            tree.add(path("/items/:id(\\d+)"), 3),
+            Result::Err(TreeError::UnreachableRoute { .. })
+        ));
+        let mut deep = path("/items/:slug/edit/:version");
+        deep.set_priority(5);
+        assert!(tree.add(deep, 4).is_ok());
+        assert!(tree.add(path("/items/:id(\\d+)/view"), 5).is_ok());
+
+        assert_eq!(
+            tree.lookup(&Method::GET, "/items/42/view")
+                .unwrap()
+                .get_item(),
+            &5
+        );
+    }
+
+    #[rstest]
+    fn test_route_priority_raised() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/items/:id(\\d+)/edit"), 1).is_ok());
+        assert!(tree.add(path("/items/:slug/edit"), 2).is_ok());
+        let mut raised = path("/items/:slug");
+        raised.set_priority(1);
+        assert!(tree.add(raised.clone(), 3).is_ok());
+        assert!(tree.add(path("/other/:id(\\d+)/edit"), 4).is_ok());
+        let mut other = path("/other/:slug");
+        other.set_priority(1);
+        assert!(tree.add(other, 5).is_ok());
+
+        let m = &Method::GET;
+        assert_eq!(tree.lookup(m, "/items/42/edit").unwrap().get_item(), &1);
+        assert_eq!(tree.lookup(m, "/items/foo/edit").unwrap().get_item(), &2);
+        assert_eq!(tree.lookup(m, "/items/42").unwrap().get_item(), &3);
+        assert_eq!(tree.lookup(m, "/other/42/edit").unwrap().get_item(), &4);
+        assert_eq!(tree.lookup(m, "/other/42").unwrap().get_item(), &5);
+
+        assert_eq!(tree.remove(&raised), Result::Ok(3));
+        assert!(tree.add(path("/items/:id(\\d+)"), 6).is_ok());
+        assert_eq!(tree.lookup(m, "/items/42").unwrap().get_item(), &6);
+        assert_eq!(tree.lookup(m, "/items/42/edit").unwrap().get_item(), &1);
+    }
+
+    #[rstest(
+        prefix,
+        expected,
+        case("/api", vec![1, 2, 3]),
+        case("/api/users/", vec![2, 3]),
+        case("api/users/:id", vec![]),
+        case("/admin", vec![]),
+        case("/", vec![0, 1, 2, 3, 4])
+    )]
+    fn test_items_under(prefix: &str, expected: Vec<i32>) {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/"), 0).is_ok());
+        assert!(tree.add(path("/api"), 1).is_ok());
+        assert!(tree.add(path("/api/users/:id"), 2).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/api/users").unwrap(), 3)
+            .is_ok());
+        assert!(tree.add(path("/apiv2"), 4).is_ok());
+
+        let mut items: Vec<i32> = tree.items_under(prefix).into_iter().copied().collect();
+        items.sort_unstable();
+
+        assert_eq!(items, expected);
+    }
+
+    #[rstest]
+    fn test_wildcard_segments() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/*path"), 1).is_ok());
+        assert!(tree.add(path("/files/:id/meta"), 2).is_ok());
+        assert!(tree.add(path("/static/*"), 3).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+        let mut options = LookupOptions::default();
+        options.set_decode_params(true);
+
+        let route_match = tree.lookup(m, "/files/foo//bar/baz/").unwrap();
+        assert_eq!(
+            route_match.wildcard_segments("path"),
+            Option::Some(vec!["foo", "bar", "baz"])
+        );
+        assert_eq!(route_match.wildcard_segments("other"), Option::None);
+        assert_eq!(
+            tree.lookup(m, "/files/foo/bar/baz")
+                .unwrap()
+                .wildcard_segments("path"),
+            Option::Some(vec!["foo", "bar", "baz"])
+        );
+        assert_eq!(
+            tree.lookup(m, "/files").unwrap().wildcard_segments("path"),
+            Option::Some(vec![])
+        );
+        assert_eq!(
+            tree.lookup(m, "/static/css/main.css")
+                .unwrap()
+                .wildcard_segments(WILDCARD_PARAMETER),
+            Option::Some(vec!["css", "main.css"])
+        );
+
+        let (route_match, _) = tree
+            .lookup_with(m, "/files/my%20dir/a%2Fb", &options)
+            .unwrap();
+        assert_eq!(route_match.get_params().get("path").unwrap(), "my dir/a/b");
+        assert_eq!(
+            route_match.wildcard_segments("path"),
+            Option::Some(vec!["my dir", "a/b"])
+        );
+        assert_eq!(
+            tree.lookup(m, "/files/42/meta")
+                .unwrap()
+                .wildcard_segments("path"),
+            Option::None
+        );
+    }
+
+    #[rstest]
+    fn test_trace() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/users/:id/posts"), 1).is_ok());
+        assert!(tree.add(path("/Files/*path"), 2).is_ok());
+
+        let tree = tree;
+        let mut options = LookupOptions::default();
+        let mut trace = Vec::new();
+        assert!(tree
+            .lookup_traced(&Method::GET, "/users/42/comments", &options, &mut trace)
+            .is_err());
+        assert_eq!(
+            trace,
+            vec![
+                TraceStep::new("users", TraceBranch::Static(String::from("users"))),
+                TraceStep::new("42", TraceBranch::Parameter(String::from("id"))),
+                TraceStep::new("comments", TraceBranch::NotFound),
+            ]
+        );
+
+        options.set_case_insensitive(true);
+        let mut trace = Vec::new();
+        assert!(tree
+            .lookup_traced(&Method::GET, "/files/a//b.txt", &options, &mut trace)
+            .is_ok());
+        assert_eq!(
+            trace,
+            vec![
+                TraceStep::new("files", TraceBranch::Static(String::from("Files"))),
+                TraceStep::new("a/b.txt", TraceBranch::Wildcard(String::from("path"))),
+            ]
+        );
+
+        let mut tree = Tree::new();
+        assert!(tree.add(path(r"/users/:id(\d+)/posts"), 1).is_ok());
+        assert!(tree.add(path("/users/:name/comments"), 2).is_ok());
+        let mut greedy = path("/api");
+        greedy.set_greedy(true);
+        assert!(tree.add(greedy, 3).is_ok());
+
+        let options = LookupOptions::default();
+        let mut trace = Vec::new();
+        assert!(tree
+            .lookup_traced(&Method::GET, "/users/42/comments", &options, &mut trace)
+            .is_ok());
+        assert_eq!(
+            trace,
+            vec![
+                TraceStep::new("users", TraceBranch::Static(String::from("users"))),
+                TraceStep::new("42", TraceBranch::Parameter(String::from("name"))),
+                TraceStep::new("comments", TraceBranch::Static(String::from("comments"))),
+            ]
+        );
+
+        let mut trace = Vec::new();
+        assert!(tree
+            .lookup_traced(&Method::GET, "/users/42/likes", &options, &mut trace)
+            .is_err());
+        assert_eq!(
+            trace,
+            vec![
+                TraceStep::new("users", TraceBranch::Static(String::from("users"))),
+                TraceStep::new("42", TraceBranch::Parameter(String::from("id"))),
+                TraceStep::new("likes", TraceBranch::NotFound),
+            ]
+        );
+
+        let mut trace = Vec::new();
+        assert!(tree
+            .lookup_traced(&Method::GET, "/api/v1/users", &options, &mut trace)
+            .is_ok());
+        assert_eq!(
+            trace,
+            vec![
+                TraceStep::new("api", TraceBranch::Static(String::from("api"))),
+                TraceStep::new("v1/users", TraceBranch::Greedy),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_backtrack_sibling_segments() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path(r"/users/:id(\d+)/posts"), 1).is_ok());
+        assert!(tree.add(path("/users/:name/comments"), 2).is_ok());
+        assert!(tree.add(path("/users/me/settings"), 3).is_ok());
+        assert!(tree.add(path("/users/*rest"), 4).is_ok());
+
+        let tree = tree;
+        let m = &Method::GET;
+        let found = tree.lookup(m, "/users/123/comments").unwrap();
+        assert_eq!(*found.get_item(), 2);
+        assert_eq!(found.get_params().get("name").unwrap(), "123");
+
+        let found = tree.lookup(m, "/users/123/posts").unwrap();
+        assert_eq!(*found.get_item(), 1);
+        assert_eq!(found.get_params().get("id").unwrap(), "123");
+
+        let found = tree.lookup(m, "/users/me/comments").unwrap();
+        assert_eq!(*found.get_item(), 2);
+        assert_eq!(found.get_params().get("name").unwrap(), "me");
+
+        let found = tree.lookup(m, "/users/123/likes").unwrap();
+        assert_eq!(*found.get_item(), 4);
+        assert_eq!(found.get_params().get("rest").unwrap(), "123/likes");
+        assert!(found.get_params().get("id").is_none());
+    }
+
+    #[rstest]
+    fn test_allowed_methods_wildcard_sibling() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/*rest"), 1).is_ok());
+        assert!(tree
+            .add(Path::parse(Method::POST, "/files/:name").unwrap(), 2)
+            .is_ok());
+        let mut greedy = Path::parse(Method::PUT, "/api").unwrap();
+        greedy.set_greedy(true);
+        assert!(tree.add(greedy, 3).is_ok());
+        assert!(tree.add(path("/api/:version/status"), 4).is_ok());
+
+        let tree = tree;
+        let options = LookupOptions::default();
+        assert!(tree.lookup(&Method::GET, "/files/a/b").is_ok());
+        assert!(tree.contains(&Method::GET, "/files/a/b", &options));
+        assert!(!tree.contains(&Method::POST, "/files/a/b", &options));
+        assert_eq!(
+            tree.allowed_methods("/files/a/b", &options).unwrap(),
+            vec![Method::GET]
+        );
+        assert_eq!(
+            tree.allowed_methods("/files/a", &options).unwrap(),
+            vec![Method::GET, Method::POST]
+        );
+
+        assert!(tree.contains(&Method::PUT, "/api/v1/status/extra", &options));
+        assert_eq!(
+            tree.allowed_methods("/api/v1/status", &options).unwrap(),
+            vec![Method::GET, Method::PUT]
+        );
+        assert_eq!(
+            tree.allowed_methods("/api/v1/other", &options).unwrap(),
+            vec![Method::PUT]
+        );
+        assert!(tree.allowed_methods("/other", &options).is_err());
+    }
+
+    #[rstest]
+    fn test_wildcard_empty_remainder() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/files/*path"), 1).is_ok());
+        assert!(tree.add(path("/static/*"), 2).is_ok());
+        assert!(tree.add(path("/docs"), 3).is_ok());
+        assert!(tree.add(path("/docs/*page"), 4).is_ok());
+
+        let tree = tree;
+        let options = LookupOptions::default();
+        let route_match = tree.lookup(&Method::GET, "/files/").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(route_match.get_params().get("path").unwrap(), "");
+        assert_eq!(
+            tree.lookup(&Method::GET, "/static/")
+                .unwrap()
+                .wildcard_remainder(),
+            Option::Some("")
+        );
+        assert_eq!(tree.lookup(&Method::GET, "/docs/").unwrap().get_item(), &3);
+        assert_eq!(tree.lookup(&Method::GET, "/docs").unwrap().get_item(), &3);
+        let route_match = tree.lookup(&Method::GET, "/files").unwrap();
+        assert_eq!(route_match.get_item(), &1);
+        assert_eq!(route_match.get_params().get("path").unwrap(), "");
+        assert!(tree.lookup(&Method::POST, "/files/").is_err());
+        assert!(tree.lookup(&Method::GET, "/file").is_err());
+
+        assert!(tree.contains(&Method::GET, "/files/", &options));
+        assert!(tree.contains(&Method::GET, "/files", &options));
+        assert!(!tree.contains(&Method::POST, "/files", &options));
+        assert_eq!(
+            tree.allowed_methods("/files/", &options).unwrap(),
+            vec![Method::GET]
+        );
+        assert_eq!(
+            tree.allowed_methods("/files", &options).unwrap(),
+            vec![Method::GET]
+        );
+    }
+
+    #[rstest]
+    fn test_conflicting_dynamic_segment() {
+        let mut tree = Tree::new();
+        assert!(tree.add(path("/a/:x"), 1).is_ok());
+        assert!(tree.add(path("/a/:x/b"), 2).is_ok());
+        assert!(tree.add(path("/c/*rest"), 3).is_ok());
+        assert!(tree.add(path("/d/:id(\\d+)"), 4).is_ok());
+
+        assert_eq!(
+            tree.add(path("/a/:y"), 5).unwrap_err(),
+            TreeError::ConflictingDynamicSegment {
+                existing: String::from(":x"),
+                incoming: String::from(":y"),
+            }
+        );
+        assert_eq!(
+            tree.add(path("/a/:y/c"), 6).unwrap_err(),
+            TreeError::ConflictingDynamicSegment {
+                existing: String::from(":x"),
+                incoming: String::from(":y"),
+            }
+        );
+        assert_eq!(
+            tree.add(path("/a/:x?"), 7).unwrap_err(),
+            TreeError::ConflictingDynamicSegment {
+                existing: String::from(":x"),
+                incoming: String::from(":x?"),
+            }
+        );
+        assert_eq!(
+            tree.add(path("/c/*path"), 8).unwrap_err(),
+            TreeError::ConflictingDynamicSegment {
+                existing: String::from("*rest"),
+                incoming: String::from("*path"),
+            }
+        );
+        assert_eq!(
+            tree.add(path("/d/:num(\\d+)"), 9).unwrap_err(),
+            TreeError::ConflictingDynamicSegment {
+                existing: String::from(":id(\\d+)"),
+                incoming: String::from(":num(\\d+)"),
+            }
+        );
+        assert_eq!(
+            tree.add(path("/a/:x"), 10).unwrap_err(),
+            TreeError::PathAlreadyRegistered {
+                route: String::from("a/:x")
+            }
+        );
+    }
 }